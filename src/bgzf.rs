@@ -0,0 +1,768 @@
+//! Blocked gzip (BGZF) reading and writing
+//!
+//! BGZF files are a series of gzip members of at most 64 KiB each, so they
+//! can be read by any gzip decoder but also support random access through
+//! virtual file offsets and `.gzi` indices.
+
+// block sizes and offsets within blocks fit in 16 bits
+#![allow(clippy::cast_possible_truncation)]
+
+use flate2::bufread::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::thread;
+
+// gzip header with the `BC` extra subfield, up to the block size
+const HEADER: [u8; 16] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, b'B', b'C', 0x02, 0x00,
+];
+const HEADER_LEN: usize = 18;
+const FOOTER_LEN: usize = 8;
+const MAX_BLOCK_LEN: usize = 0x1_0000;
+// uncompressed bytes per block, leaving room for incompressible data
+const MAX_DATA_LEN: usize = 0xff00;
+
+/// An empty block which marks the end of a BGZF file
+pub const EOF_BLOCK: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// A position in a BGZF file: the offset of a block in the compressed file
+/// and an offset into that block's uncompressed data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct VirtualOffset(u64);
+
+impl VirtualOffset {
+    pub fn new(block_offset: u64, within_block: u16) -> Self {
+        VirtualOffset((block_offset << 16) | u64::from(within_block))
+    }
+
+    /// Offset of the block in the compressed file
+    pub fn block_offset(self) -> u64 {
+        self.0 >> 16
+    }
+
+    /// Offset into the block's uncompressed data
+    pub fn within_block(self) -> u16 {
+        (self.0 & 0xffff) as u16
+    }
+}
+
+impl From<u64> for VirtualOffset {
+    fn from(offset: u64) -> Self {
+        VirtualOffset(offset)
+    }
+}
+
+impl From<VirtualOffset> for u64 {
+    fn from(offset: VirtualOffset) -> Self {
+        offset.0
+    }
+}
+
+// header fields of a block needed to find its data
+struct BlockHeader {
+    // total length of the block, header and footer included
+    len: usize,
+    xlen: usize,
+}
+
+// read a block header, or `None` at the end of the input
+fn read_header<R: Read>(reader: &mut R) -> io::Result<Option<BlockHeader>> {
+    let mut header = [0u8; 12];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    if header[..4] != HEADER[..4] {
+        return Err(invalid_data("not a BGZF block"));
+    }
+
+    let xlen = usize::from(u16::from_le_bytes([header[10], header[11]]));
+    let mut extra = vec![0u8; xlen];
+    reader.read_exact(&mut extra)?;
+
+    // find the `BC` subfield among the extra fields
+    let mut fields = &extra[..];
+    while let [si1, si2, l1, l2, rest @ ..] = fields {
+        let slen = usize::from(u16::from_le_bytes([*l1, *l2]));
+        if rest.len() < slen {
+            break;
+        }
+        if (*si1, *si2, slen) == (b'B', b'C', 2) {
+            let len = usize::from(u16::from_le_bytes([rest[0], rest[1]])) + 1;
+            if len < 12 + xlen + FOOTER_LEN {
+                return Err(invalid_data("BGZF block size is too small"));
+            }
+            return Ok(Some(BlockHeader { len, xlen }));
+        }
+        fields = &rest[slen..];
+    }
+
+    Err(invalid_data("gzip member has no BGZF block size"))
+}
+
+/// Reader of BGZF compressed data. Implements `BufRead` so it can be used
+/// underneath `FastqReader` and `FastaReader`.
+pub struct BgzfReader<R: Read> {
+    reader: R,
+    // uncompressed data of the current block
+    block: Vec<u8>,
+    cdata: Vec<u8>,
+    pos: usize,
+    block_offset: u64,
+    next_block: u64,
+    // uncompressed offset of the current block, if known
+    block_start: Option<u64>,
+    index: Option<Gzi>,
+}
+
+impl<R: Read> BgzfReader<R> {
+    pub fn new(reader: R) -> Self {
+        BgzfReader {
+            reader,
+            block: Vec::with_capacity(MAX_BLOCK_LEN),
+            cdata: Vec::with_capacity(MAX_BLOCK_LEN),
+            pos: 0,
+            block_offset: 0,
+            next_block: 0,
+            block_start: Some(0),
+            index: None,
+        }
+    }
+
+    /// Use a `.gzi` index to seek by uncompressed offset
+    #[must_use]
+    pub fn with_index(mut self, index: Gzi) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// The virtual offset of the next byte to be read
+    pub fn virtual_offset(&self) -> VirtualOffset {
+        if self.pos < self.block.len() {
+            // blocks hold fewer than 2^16 bytes
+            VirtualOffset::new(self.block_offset, self.pos as u16)
+        } else {
+            VirtualOffset::new(self.next_block, 0)
+        }
+    }
+
+    // decompress the next block, returning `false` at the end of the input
+    fn read_block(&mut self) -> io::Result<bool> {
+        let Some(header) = read_header(&mut self.reader)? else {
+            return Ok(false);
+        };
+
+        self.cdata.resize(header.len - 12 - header.xlen, 0);
+        self.reader.read_exact(&mut self.cdata)?;
+
+        let (cdata, footer) = self.cdata.split_at(self.cdata.len() - FOOTER_LEN);
+        let crc = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+        let isize = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]) as usize;
+
+        self.block.clear();
+        DeflateDecoder::new(cdata).read_to_end(&mut self.block)?;
+
+        let mut check = Crc::new();
+        check.update(&self.block);
+        if self.block.len() != isize || check.sum() != crc {
+            return Err(invalid_data("BGZF block failed its integrity check"));
+        }
+
+        self.block_offset = self.next_block;
+        self.next_block += header.len as u64;
+        self.pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> BgzfReader<R> {
+    /// Move to a virtual offset
+    ///
+    /// # Errors
+    /// Fails if the block can't be read or the offset is past its end
+    pub fn seek_virtual(&mut self, offset: VirtualOffset) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(offset.block_offset()))?;
+        self.next_block = offset.block_offset();
+        self.block.clear();
+        self.pos = 0;
+        self.block_start = self
+            .index
+            .as_ref()
+            .and_then(|index| index.uncompressed_offset(offset.block_offset()));
+
+        let within = usize::from(offset.within_block());
+        if !self.read_block()? && within == 0 {
+            return Ok(());
+        }
+        if within > self.block.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "virtual offset is past the end of its block",
+            ));
+        }
+        self.pos = within;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for BgzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.fill_buf()?;
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for BgzfReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // skip over empty blocks, like the end of file marker
+        while self.pos == self.block.len() {
+            let len = self.block.len() as u64;
+            if !self.read_block()? {
+                break;
+            }
+            self.block_start = self.block_start.map(|start| start + len);
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.block.len());
+    }
+}
+
+/// Seeking by uncompressed offset requires a `.gzi` index, except for
+/// rewinding to the start
+impl<R: Read + Seek> Seek for BgzfReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(target) => target,
+            SeekFrom::Current(delta) => self
+                .block_start
+                .map(|start| start + self.pos as u64)
+                .and_then(|current| current.checked_add_signed(delta))
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "invalid BGZF seek offset")
+                })?,
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "BGZF streams can't seek from the end",
+                ))
+            }
+        };
+
+        let offset = match &self.index {
+            Some(index) => index.virtual_offset(target).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "seek offset is past the end of the BGZF data",
+                )
+            })?,
+            None if target == 0 => VirtualOffset::default(),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "seeking in BGZF data requires a .gzi index",
+                ))
+            }
+        };
+
+        self.seek_virtual(offset)?;
+        self.block_start = Some(target - u64::from(offset.within_block()));
+        Ok(target)
+    }
+}
+
+/// Index of BGZF block offsets, as written by `bgzip -i`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gzi {
+    // (compressed, uncompressed) offsets of each block, starting with (0, 0)
+    blocks: Vec<(u64, u64)>,
+}
+
+impl Default for Gzi {
+    fn default() -> Self {
+        Gzi {
+            blocks: vec![(0, 0)],
+        }
+    }
+}
+
+impl Gzi {
+    /// Offsets of each block after the first, as (compressed, uncompressed)
+    pub fn blocks(&self) -> &[(u64, u64)] {
+        &self.blocks[1..]
+    }
+
+    /// The virtual offset of a position in the uncompressed data, or `None`
+    /// if it is too far past the start of the last block to be in it
+    pub fn virtual_offset(&self, pos: u64) -> Option<VirtualOffset> {
+        let i = self.blocks.partition_point(|&(_, u)| u <= pos) - 1;
+        let (block, start) = self.blocks[i];
+        // blocks hold at most 2^16 bytes
+        let within = u16::try_from(pos - start).ok()?;
+        Some(VirtualOffset::new(block, within))
+    }
+
+    fn uncompressed_offset(&self, block: u64) -> Option<u64> {
+        self.blocks
+            .binary_search_by_key(&block, |&(c, _)| c)
+            .ok()
+            .map(|i| self.blocks[i].1)
+    }
+
+    fn push(&mut self, compressed: u64, uncompressed: u64) {
+        self.blocks.push((compressed, uncompressed));
+    }
+
+    /// # Errors
+    /// Propagates I/O errors and fails on truncated indices
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut word = [0u8; 8];
+        reader.read_exact(&mut word)?;
+        let n = u64::from_le_bytes(word);
+
+        let mut index = Gzi::default();
+        for _ in 0..n {
+            reader.read_exact(&mut word)?;
+            let compressed = u64::from_le_bytes(word);
+            reader.read_exact(&mut word)?;
+            index.push(compressed, u64::from_le_bytes(word));
+        }
+        Ok(index)
+    }
+
+    /// # Errors
+    /// Fails if the file can't be opened or is not a valid index
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Gzi::read(io::BufReader::new(File::open(path)?))
+    }
+
+    /// # Errors
+    /// Propagates errors from the underlying writer
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&(self.blocks().len() as u64).to_le_bytes())?;
+        for (compressed, uncompressed) in self.blocks() {
+            writer.write_all(&compressed.to_le_bytes())?;
+            writer.write_all(&uncompressed.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Index a BGZF file by scanning its block headers. The data isn't
+    /// decompressed.
+    ///
+    /// # Errors
+    /// Propagates I/O errors and fails if the input isn't BGZF
+    pub fn build<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut index = Gzi::default();
+        let mut compressed = 0;
+        let mut uncompressed = 0;
+        let mut skip = Vec::with_capacity(MAX_BLOCK_LEN);
+
+        while let Some(header) = read_header(&mut reader)? {
+            skip.resize(header.len - 12 - header.xlen, 0);
+            reader.read_exact(&mut skip)?;
+            let footer = &skip[skip.len() - 4..];
+            let isize = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+
+            if compressed > 0 && isize > 0 {
+                index.push(compressed, uncompressed);
+            }
+            compressed += header.len as u64;
+            uncompressed += u64::from(isize);
+        }
+        Ok(index)
+    }
+}
+
+// compress one block of at most `MAX_DATA_LEN` bytes
+fn compress_block(data: &[u8], level: Compression) -> io::Result<Vec<u8>> {
+    let mut block = Vec::with_capacity(MAX_BLOCK_LEN);
+    block.extend_from_slice(&HEADER);
+    block.extend_from_slice(&[0, 0]);
+
+    let mut encoder = DeflateEncoder::new(block, level);
+    encoder.write_all(data)?;
+    let mut block = encoder.finish()?;
+
+    if block.len() + FOOTER_LEN > MAX_BLOCK_LEN {
+        // store incompressible data as is
+        block.truncate(HEADER_LEN);
+        let mut encoder = DeflateEncoder::new(block, Compression::none());
+        encoder.write_all(data)?;
+        block = encoder.finish()?;
+    }
+
+    let mut crc = Crc::new();
+    crc.update(data);
+    block.extend_from_slice(&crc.sum().to_le_bytes());
+    block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+    let bsize = (block.len() - 1) as u16;
+    block[16..HEADER_LEN].copy_from_slice(&bsize.to_le_bytes());
+    Ok(block)
+}
+
+/// Writer of BGZF compressed data. Blocks can be compressed in parallel.
+///
+/// The end of file marker is written by `finish`, or when the writer is
+/// dropped.
+pub struct BgzfWriter<W: Write> {
+    writer: Option<W>,
+    level: Compression,
+    threads: usize,
+    block: Vec<u8>,
+    // full blocks waiting to be compressed
+    pending: Vec<Vec<u8>>,
+    compressed: u64,
+    uncompressed: u64,
+    index: Gzi,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(writer: W) -> Self {
+        BgzfWriter {
+            writer: Some(writer),
+            level: Compression::default(),
+            threads: 1,
+            block: Vec::with_capacity(MAX_DATA_LEN),
+            pending: Vec::new(),
+            compressed: 0,
+            uncompressed: 0,
+            index: Gzi::default(),
+        }
+    }
+
+    /// Compression level from 0 (none) to 9 (best)
+    #[must_use]
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Compression::new(level.min(9));
+        self
+    }
+
+    /// Number of blocks to compress in parallel
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// The virtual offset of the next byte to be written. Blocks waiting to
+    /// be compressed in parallel are written first, since their compressed
+    /// sizes aren't known until then.
+    ///
+    /// # Errors
+    /// Propagates errors from the underlying writer
+    pub fn virtual_offset(&mut self) -> io::Result<VirtualOffset> {
+        self.compress_pending()?;
+        // blocks hold fewer than 2^16 bytes
+        Ok(VirtualOffset::new(self.compressed, self.block.len() as u16))
+    }
+
+    /// Index of the blocks written so far
+    pub fn index(&self) -> &Gzi {
+        &self.index
+    }
+
+    fn compress_pending(&mut self) -> io::Result<()> {
+        let level = self.level;
+        let blocks: Vec<io::Result<Vec<u8>>> = if self.pending.len() > 1 {
+            thread::scope(|s| {
+                let handles: Vec<_> = self
+                    .pending
+                    .iter()
+                    .map(|data| s.spawn(move || compress_block(data, level)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                    .collect()
+            })
+        } else {
+            self.pending
+                .iter()
+                .map(|data| compress_block(data, level))
+                .collect()
+        };
+
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("writer is present until finished");
+        for (data, block) in self.pending.drain(..).zip(blocks) {
+            let block = block?;
+            if self.compressed > 0 {
+                self.index.push(self.compressed, self.uncompressed);
+            }
+            writer.write_all(&block)?;
+            self.compressed += block.len() as u64;
+            self.uncompressed += data.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn end_block(&mut self) -> io::Result<()> {
+        if !self.block.is_empty() {
+            let block = std::mem::replace(&mut self.block, Vec::with_capacity(MAX_DATA_LEN));
+            self.pending.push(block);
+        }
+        self.compress_pending()
+    }
+
+    fn write_eof(&mut self) -> io::Result<()> {
+        self.end_block()?;
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("writer is present until finished");
+        writer.write_all(&EOF_BLOCK)?;
+        writer.flush()
+    }
+
+    /// Write any buffered data and the end of file marker
+    ///
+    /// # Errors
+    /// Propagates errors from the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_eof()?;
+        match self.writer.take() {
+            Some(writer) => Ok(writer),
+            None => unreachable!("writer is present until finished"),
+        }
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(MAX_DATA_LEN - self.block.len());
+        self.block.extend_from_slice(&buf[..n]);
+
+        if self.block.len() == MAX_DATA_LEN {
+            let block = std::mem::replace(&mut self.block, Vec::with_capacity(MAX_DATA_LEN));
+            self.pending.push(block);
+            if self.pending.len() >= self.threads {
+                self.compress_pending()?;
+            }
+        }
+        Ok(n)
+    }
+
+    /// Ends the current block and writes all buffered data
+    fn flush(&mut self) -> io::Result<()> {
+        self.end_block()?;
+        self.writer
+            .as_mut()
+            .expect("writer is present until finished")
+            .flush()
+    }
+}
+
+impl<W: Write> Drop for BgzfWriter<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _ = self.write_eof();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::{self, Compression as Format};
+    use crate::fastq::FastqReader;
+    use std::io::Cursor;
+
+    const FQ: &[u8] = b"@SEQ_ID_1\nACTCGATCGCGACGAA\n+\nAFFFFFFFFFFFFEBA\n";
+
+    // enough records to span several blocks
+    fn fastq(n: usize) -> Vec<u8> {
+        (0..n)
+            .flat_map(|i| format!("@read_{i}\nACGTTGCAACGT\n+\nIIIIII!!!!!!\n").into_bytes())
+            .collect()
+    }
+
+    fn compress(data: &[u8], threads: usize) -> (Vec<u8>, Gzi) {
+        let mut writer = BgzfWriter::new(Vec::new()).level(6).threads(threads);
+        writer.write_all(data).unwrap();
+        writer.flush().unwrap();
+        let index = writer.index().clone();
+        (writer.finish().unwrap(), index)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (bgzf, _) = compress(FQ, 1);
+        assert_eq!(Format::detect(&bgzf), Format::Bgzf);
+        assert!(bgzf.ends_with(&EOF_BLOCK));
+
+        let mut out = Vec::new();
+        BgzfReader::new(Cursor::new(&bgzf))
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, FQ);
+
+        // BGZF is valid multi-member gzip
+        let mut out = Vec::new();
+        compression::decompress(Cursor::new(bgzf))
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, FQ);
+
+        let (empty, _) = compress(b"", 1);
+        assert_eq!(empty, EOF_BLOCK);
+    }
+
+    #[test]
+    fn test_threads() {
+        let data = fastq(20_000);
+        let (single, index) = compress(&data, 1);
+        let (parallel, parallel_index) = compress(&data, 4);
+
+        assert_eq!(single, parallel);
+        assert_eq!(index, parallel_index);
+        assert!(index.blocks().len() > 4);
+        assert_eq!(index, Gzi::build(Cursor::new(&single)).unwrap());
+
+        let mut reader: FastqReader<BgzfReader<Cursor<Vec<u8>>>> =
+            FastqReader::new(BgzfReader::new(Cursor::new(parallel)));
+        assert_eq!((&mut reader).map(Result::unwrap).count(), 20_000);
+    }
+
+    #[test]
+    fn test_incompressible() {
+        // a xorshift sequence doesn't compress
+        let mut x: u32 = 2_463_534_242;
+        let data: Vec<u8> = (0..200_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x.to_le_bytes()[0]
+            })
+            .collect();
+
+        let (bgzf, _) = compress(&data, 2);
+        let mut out = Vec::new();
+        BgzfReader::new(Cursor::new(bgzf))
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_virtual_offsets() {
+        let mut writer = BgzfWriter::new(Vec::new()).threads(2);
+        let mut offsets = Vec::new();
+        for i in 0..10_000 {
+            offsets.push(writer.virtual_offset().unwrap());
+            write!(writer, "@read_{i}\nACGT\n+\nIIII\n").unwrap();
+        }
+        let bgzf = writer.finish().unwrap();
+        assert!(offsets.last().unwrap().block_offset() > 0);
+
+        let mut reader = BgzfReader::new(Cursor::new(bgzf));
+        let mut line = String::new();
+        for i in [9_999, 0, 4_321, 6_000] {
+            reader.seek_virtual(offsets[i]).unwrap();
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, format!("@read_{i}\n"));
+        }
+
+        reader.seek_virtual(offsets[17]).unwrap();
+        assert_eq!(reader.virtual_offset(), offsets[17]);
+
+        let offset = VirtualOffset::new(0x1234, 0x56);
+        assert_eq!(u64::from(offset), 0x1234_0056);
+        assert_eq!(VirtualOffset::from(0x1234_0056), offset);
+    }
+
+    #[test]
+    fn test_seek() {
+        let data = fastq(20_000);
+        let (bgzf, index) = compress(&data, 1);
+
+        let mut reader = BgzfReader::new(Cursor::new(bgzf.clone()));
+        assert!(reader.seek(SeekFrom::Start(100)).is_err());
+        reader.rewind().unwrap();
+
+        let mut reader = reader.with_index(index);
+        let mut buf = [0u8; 1000];
+        for pos in [0, 65_279, 65_280, 123_456, data.len() as u64 - 1000] {
+            reader.seek(SeekFrom::Start(pos)).unwrap();
+            reader.read_exact(&mut buf).unwrap();
+            let pos = pos as usize;
+            assert_eq!(buf[..], data[pos..pos + 1000]);
+            assert_eq!(reader.stream_position().unwrap(), pos as u64 + 1000);
+        }
+
+        reader.seek(SeekFrom::Current(-2000)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[..], data[data.len() - 2000..data.len() - 1000]);
+
+        // past the end of the last block
+        for pos in [data.len() as u64 + 1, data.len() as u64 + 100_000] {
+            assert_eq!(
+                reader.seek(SeekFrom::Start(pos)).unwrap_err().kind(),
+                io::ErrorKind::InvalidInput
+            );
+        }
+    }
+
+    #[test]
+    fn test_gzi() {
+        let (_, index) = compress(&fastq(20_000), 1);
+
+        let mut out = Vec::new();
+        index.write(&mut out).unwrap();
+        assert_eq!(out.len(), 8 + 16 * index.blocks().len());
+        assert_eq!(Gzi::read(Cursor::new(&out)).unwrap(), index);
+
+        assert!(Gzi::read(Cursor::new(&out[..out.len() - 1])).is_err());
+        assert!(Gzi::build(Cursor::new(FQ)).is_err());
+
+        let (block, start) = *index.blocks().last().unwrap();
+        assert_eq!(
+            index.virtual_offset(start + 10),
+            Some(VirtualOffset::new(block, 10))
+        );
+        assert_eq!(index.virtual_offset(99), Some(VirtualOffset::new(0, 99)));
+        assert_eq!(
+            index.virtual_offset(start + 0xffff),
+            Some(VirtualOffset::new(block, 0xffff))
+        );
+        assert_eq!(index.virtual_offset(start + 0x1_0000), None);
+        assert_eq!(index.virtual_offset(u64::MAX), None);
+    }
+}
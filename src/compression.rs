@@ -0,0 +1,214 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+
+/// Compression formats recognised from the first bytes of a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    /// block gzip, as written by `bgzip`. Decoded as multi-member gzip.
+    Bgzf,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+// number of bytes needed to recognise every supported format
+const MAGIC_LEN: usize = 18;
+
+impl Compression {
+    /// Guess the compression format from the magic bytes at the start of a stream
+    pub fn detect(magic: &[u8]) -> Self {
+        match magic {
+            [0x1f, 0x8b, 0x08, flags, ..] => {
+                // an `FEXTRA` field whose first subfield is `BC`
+                if flags & 0x04 != 0 && magic.get(12..14) == Some(b"BC") {
+                    Compression::Bgzf
+                } else {
+                    Compression::Gzip
+                }
+            }
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Compression::Zstd,
+            [b'B', b'Z', b'h', ..] => Compression::Bzip2,
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Compression::Xz,
+            _ => Compression::None,
+        }
+    }
+}
+
+#[cfg(not(all(feature = "gzip", feature = "zstd", feature = "bzip2", feature = "xz")))]
+fn unsupported(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("reading {feature} compressed input requires the `{feature}` feature"),
+    )
+}
+
+/// Sniff the compression format of `reader` and wrap it in the matching
+/// decoder. Uncompressed input is passed through.
+///
+/// # Errors
+/// Fails on I/O errors while reading the magic bytes, or if the input is
+/// compressed with a format whose cargo feature is not enabled
+pub fn decompress<R: Read + Send + 'static>(mut reader: R) -> io::Result<Box<dyn BufRead + Send>> {
+    let mut magic = Vec::with_capacity(MAGIC_LEN);
+    (&mut reader)
+        .take(MAGIC_LEN as u64)
+        .read_to_end(&mut magic)?;
+
+    let compression = Compression::detect(&magic);
+    let reader = Cursor::new(magic).chain(reader);
+
+    Ok(match compression {
+        Compression::None => Box::new(BufReader::new(reader)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip | Compression::Bgzf => {
+            Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(reader)))
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(BufReader::new(zstd::stream::read::Decoder::new(reader)?)),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Box::new(BufReader::new(bzip2::read::MultiBzDecoder::new(reader))),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Box::new(BufReader::new(liblzma::read::XzDecoder::new_multi_decoder(
+            reader,
+        ))),
+        #[cfg(not(feature = "gzip"))]
+        Compression::Gzip | Compression::Bgzf => return Err(unsupported("gzip")),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => return Err(unsupported("zstd")),
+        #[cfg(not(feature = "bzip2"))]
+        Compression::Bzip2 => return Err(unsupported("bzip2")),
+        #[cfg(not(feature = "xz"))]
+        Compression::Xz => return Err(unsupported("xz")),
+    })
+}
+
+/// Open a file, transparently decompressing it
+///
+/// # Errors
+/// Fails if the file can't be opened or its compression format isn't supported
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead + Send>> {
+    decompress(File::open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fasta::FastaReader;
+    use crate::fastq::FastqReader;
+
+    const FQ: &[u8] = b"@SEQ_ID_1\nACTCGATCGCGACGAA\n+\nAFFFFFFFFFFFFEBA\n";
+    const FQ2: &[u8] = b"@SEQ_ID_2\nCATCGACTACGGCG\n+\nGGGGGGGGGGGGGG\n";
+
+    fn read_all(reader: Box<dyn BufRead + Send>) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut reader = reader;
+        reader.read_to_end(&mut out).unwrap();
+        out
+    }
+
+    fn fastq_ids(data: Vec<u8>) -> Vec<Vec<u8>> {
+        let mut reader: FastqReader<Box<dyn BufRead + Send>> =
+            FastqReader::from_reader(Cursor::new(data)).unwrap();
+        (&mut reader).map(|r| r.unwrap().raw_fields).collect()
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        assert_eq!(Compression::detect(FQ), Compression::None);
+        assert_eq!(read_all(decompress(Cursor::new(FQ)).unwrap()), FQ);
+        assert_eq!(read_all(decompress(Cursor::new(b"@")).unwrap()), b"@");
+        assert_eq!(read_all(decompress(io::empty()).unwrap()), b"");
+        assert_eq!(
+            fastq_ids([FQ, FQ2].concat()),
+            [b"SEQ_ID_1".to_vec(), b"SEQ_ID_2".to_vec()]
+        );
+
+        let mut fasta: FastaReader<Box<dyn BufRead + Send>> =
+            FastaReader::from_reader(Cursor::new(b">SEQ_ID_1\nACGT\n")).unwrap();
+        assert_eq!((&mut fasta).count(), 1);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_multi_member_gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut data = Vec::new();
+        for member in [FQ, FQ2] {
+            let mut gz = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            gz.write_all(member).unwrap();
+            data.extend(gz.finish().unwrap());
+        }
+
+        assert_eq!(Compression::detect(&data), Compression::Gzip);
+        assert_eq!(
+            fastq_ids(data),
+            [b"SEQ_ID_1".to_vec(), b"SEQ_ID_2".to_vec()]
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_bgzf() {
+        // an empty BGZF block, as used for the end-of-file marker
+        let eof: &[u8] = &[
+            0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43,
+            0x02, 0x00, 0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        assert_eq!(Compression::detect(eof), Compression::Bgzf);
+        assert_eq!(read_all(decompress(Cursor::new(eof)).unwrap()), b"");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        let mut data = zstd::stream::encode_all(FQ, 3).unwrap();
+        data.extend(zstd::stream::encode_all(FQ2, 3).unwrap());
+
+        assert_eq!(Compression::detect(&data), Compression::Zstd);
+        assert_eq!(
+            fastq_ids(data),
+            [b"SEQ_ID_1".to_vec(), b"SEQ_ID_2".to_vec()]
+        );
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn test_bzip2() {
+        use std::io::Write;
+
+        let mut bz = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bz.write_all(FQ).unwrap();
+        let data = bz.finish().unwrap();
+
+        assert_eq!(Compression::detect(&data), Compression::Bzip2);
+        assert_eq!(fastq_ids(data), [b"SEQ_ID_1".to_vec()]);
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_xz() {
+        use std::io::Write;
+
+        let mut xz = liblzma::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(FQ).unwrap();
+        let data = xz.finish().unwrap();
+
+        assert_eq!(Compression::detect(&data), Compression::Xz);
+        assert_eq!(fastq_ids(data), [b"SEQ_ID_1".to_vec()]);
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn test_unsupported() {
+        let data: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd, 0x00];
+        let e = decompress(Cursor::new(data)).err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::Unsupported);
+    }
+}
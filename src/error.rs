@@ -1,6 +1,32 @@
 #![allow(clippy::module_name_repetitions)]
-use core::error::Error;
+use bio_seq::error::ParseBioError;
+use core::convert::Infallible;
 use core::fmt;
+use std::io;
+
+// longest snippet of offending input kept in an `Error`
+const SNIPPET_LEN: usize = 80;
+
+/// Location of a parse error in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    /// 0-based index of the record
+    pub record: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 0-based byte offset from the start of the input
+    pub offset: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "record {}, line {}, byte {}",
+            self.record, self.line, self.offset
+        )
+    }
+}
 
 #[derive(Debug)]
 pub enum ParseError {
@@ -11,8 +37,36 @@ pub enum ParseError {
     InvalidQuality,
     FileError,
     InvalidFields,
+    InvalidHeader(Position),
+    InvalidSeparator(Position),
+    MismatchedIds(Position),
+    MismatchedLengths(Position),
+    InvalidBase(u8, Position),
+    InvalidQualityScore(u8, Position),
+    Truncated(Position),
+    /// a paired record with no mate: the index of the pair, and whether the
+    /// missing mate is read 1 or 2
+    MissingMate(usize, u8),
+    /// the names of the mates at this pair index differ
+    MismatchedMates(usize),
+}
+impl core::error::Error for ParseError {}
+
+impl ParseError {
+    /// The location of the error, if it is known
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Self::InvalidHeader(pos)
+            | Self::InvalidSeparator(pos)
+            | Self::MismatchedIds(pos)
+            | Self::MismatchedLengths(pos)
+            | Self::InvalidBase(_, pos)
+            | Self::InvalidQualityScore(_, pos)
+            | Self::Truncated(pos) => Some(*pos),
+            _ => None,
+        }
+    }
 }
-impl Error for ParseError {}
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -24,6 +78,150 @@ impl fmt::Display for ParseError {
             Self::InvalidQuality => write!(f, "Invalid quailty string"),
             Self::FileError => write!(f, "File error"),
             Self::InvalidFields => write!(f, "Invalid data fields"),
+            Self::InvalidHeader(pos) => write!(f, "Invalid header at {pos}"),
+            Self::InvalidSeparator(pos) => write!(f, "Invalid separator line at {pos}"),
+            Self::MismatchedIds(pos) => {
+                write!(f, "Separator id does not match header at {pos}")
+            }
+            Self::MismatchedLengths(pos) => {
+                write!(f, "Quality and sequence lengths differ at {pos}")
+            }
+            Self::InvalidBase(b, pos) => {
+                write!(f, "Invalid base {:?} at {pos}", char::from(*b))
+            }
+            Self::InvalidQualityScore(q, pos) => {
+                write!(f, "Invalid quality score {:?} at {pos}", char::from(*q))
+            }
+            Self::Truncated(pos) => write!(f, "Truncated record at {pos}"),
+            Self::MissingMate(pair, mate) => {
+                write!(f, "Read {mate} is missing for pair {pair}")
+            }
+            Self::MismatchedMates(pair) => write!(f, "Mate names differ for pair {pair}"),
+        }
+    }
+}
+
+impl From<Infallible> for ParseError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+impl From<ParseBioError> for ParseError {
+    fn from(e: ParseBioError) -> Self {
+        ParseError::InvalidSequence(e.to_string())
+    }
+}
+
+/// The file format being parsed when an error occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Fastq,
+    Fasta,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fastq => write!(f, "FASTQ"),
+            Self::Fasta => write!(f, "FASTA"),
+        }
+    }
+}
+
+/// Errors produced by the readers: either an I/O failure or malformed input
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse {
+        format: Format,
+        error: ParseError,
+        /// the start of the offending line, if available
+        snippet: Option<Vec<u8>>,
+    },
+}
+
+impl Error {
+    pub(crate) fn parse(format: Format, error: ParseError, snippet: Option<&[u8]>) -> Self {
+        Error::Parse {
+            format,
+            error,
+            snippet: snippet.map(|s| s[..s.len().min(SNIPPET_LEN)].to_vec()),
+        }
+    }
+
+    pub fn format(&self) -> Option<Format> {
+        match self {
+            Self::Io(_) => None,
+            Self::Parse { format, .. } => Some(*format),
+        }
+    }
+
+    pub fn parse_error(&self) -> Option<&ParseError> {
+        match self {
+            Self::Io(_) => None,
+            Self::Parse { error, .. } => Some(error),
+        }
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        self.parse_error().and_then(ParseError::position)
+    }
+
+    pub fn snippet(&self) -> Option<&[u8]> {
+        match self {
+            Self::Io(_) => None,
+            Self::Parse { snippet, .. } => snippet.as_deref(),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Parse {
+                format,
+                error,
+                snippet: None,
+            } => write!(f, "{format} parse error: {error}"),
+            Self::Parse {
+                format,
+                error,
+                snippet: Some(snippet),
+            } => write!(
+                f,
+                "{format} parse error: {error}: {:?}",
+                String::from_utf8_lossy(snippet)
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            Error::Parse {
+                error: ParseError::Truncated(_) | ParseError::TruncatedRecord,
+                ..
+            } => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            Error::Parse { .. } => io::Error::new(io::ErrorKind::InvalidData, e),
         }
     }
 }
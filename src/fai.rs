@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+#[cfg(feature = "gzip")]
+use crate::bgzf::{BgzfReader, Gzi};
+pub use crate::error::ParseError;
+pub use crate::record::Record;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// A single line of a samtools-compatible `.fai` index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaiRecord {
+    pub name: String,
+    /// total number of bases in the sequence
+    pub length: u64,
+    /// byte offset of the first base of the sequence
+    pub offset: u64,
+    /// number of bases on each line
+    pub line_bases: u64,
+    /// number of bytes on each line, including the line terminator
+    pub line_width: u64,
+}
+
+impl FaiRecord {
+    /// Byte offset in the FASTA file of the base at (0-based) position `pos`
+    pub fn byte_offset(&self, pos: u64) -> u64 {
+        if self.line_bases == 0 {
+            return self.offset;
+        }
+        self.offset + (pos / self.line_bases) * self.line_width + pos % self.line_bases
+    }
+}
+
+/// Index of sequence names to their locations in a FASTA file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fai {
+    records: Vec<FaiRecord>,
+    names: HashMap<String, usize>,
+}
+
+impl Fai {
+    pub fn new(records: Vec<FaiRecord>) -> Self {
+        let names = records
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.name.clone(), i))
+            .collect();
+        Fai { records, names }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FaiRecord> {
+        self.names.get(name).map(|&i| &self.records[i])
+    }
+
+    pub fn records(&self) -> &[FaiRecord] {
+        &self.records
+    }
+
+    /// Read a five column `.fai` index
+    ///
+    /// # Errors
+    /// Fails on I/O errors and on lines that do not have five tab separated columns
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut records = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+
+            let cols: Vec<&str> = line.split('\t').collect();
+            if cols.len() != 5 {
+                return Err(invalid_data(format!("Invalid FAI line: {line}")));
+            }
+
+            let num = |s: &str| s.parse::<u64>().map_err(invalid_data);
+
+            records.push(FaiRecord {
+                name: cols[0].to_string(),
+                length: num(cols[1])?,
+                offset: num(cols[2])?,
+                line_bases: num(cols[3])?,
+                line_width: num(cols[4])?,
+            });
+        }
+
+        Ok(Fai::new(records))
+    }
+
+    /// # Errors
+    /// Fails if the index file can't be opened or is malformed
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Fai::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Write the index in the `samtools faidx` format
+    ///
+    /// # Errors
+    /// Propagates errors from the underlying writer
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for r in &self.records {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                r.name, r.length, r.offset, r.line_bases, r.line_width
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Build an index by scanning a FASTA file
+    ///
+    /// # Errors
+    /// Fails on I/O errors, on sequence data before the first header, and on
+    /// records whose lines (other than the last) are not all the same length
+    pub fn build<R: BufRead>(mut reader: R) -> io::Result<Self> {
+        let mut records: Vec<FaiRecord> = Vec::new();
+        let mut line = Vec::<u8>::with_capacity(1024);
+        let mut pos: u64 = 0;
+
+        // set once a record has a line shorter than its first line
+        let mut short_line = false;
+
+        loop {
+            line.clear();
+            let n = reader.read_until(b'\n', &mut line)?;
+            if n == 0 {
+                break;
+            }
+            let start = pos;
+            pos += n as u64;
+
+            let width = n as u64;
+            let bases = line
+                .strip_suffix(b"\n")
+                .map_or(&line[..], |l| l.strip_suffix(b"\r").unwrap_or(l));
+
+            if bases.first() == Some(&b'>') {
+                let name = bases[1..]
+                    .split(u8::is_ascii_whitespace)
+                    .next()
+                    .unwrap_or_default();
+                records.push(FaiRecord {
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    length: 0,
+                    offset: pos,
+                    line_bases: 0,
+                    line_width: 0,
+                });
+                short_line = false;
+                continue;
+            }
+
+            let Some(record) = records.last_mut() else {
+                if bases.is_empty() {
+                    continue;
+                }
+                return Err(invalid_data(format!(
+                    "FASTA sequence data before first header at byte {start}"
+                )));
+            };
+
+            if bases.is_empty() {
+                short_line = true;
+                continue;
+            }
+
+            let bases = bases.len() as u64;
+            if record.line_bases == 0 {
+                record.line_bases = bases;
+                record.line_width = width;
+            } else if short_line || bases > record.line_bases || width > record.line_width {
+                return Err(invalid_data(format!(
+                    "Inconsistent line lengths in FASTA record {} at byte {start}",
+                    record.name
+                )));
+            } else if bases < record.line_bases || width < record.line_width {
+                short_line = true;
+            }
+            record.length += bases;
+        }
+
+        Ok(Fai::new(records))
+    }
+}
+
+/// Random access to regions of a FASTA file through its `.fai` index
+pub struct IndexedFasta<R: Read + Seek> {
+    reader: R,
+    index: Fai,
+    buffer: Vec<u8>,
+}
+
+impl IndexedFasta<BufReader<File>> {
+    /// Open a FASTA file and its index at `<path>.fai`, building the index if
+    /// the file doesn't exist
+    ///
+    /// # Errors
+    /// Fails if either file can't be read or the index can't be built
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut fai_path = path.as_os_str().to_owned();
+        fai_path.push(".fai");
+
+        let mut reader = BufReader::new(File::open(path)?);
+        let index = match Fai::from_path(&fai_path) {
+            Ok(index) => index,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let index = Fai::build(&mut reader)?;
+                reader.rewind()?;
+                index
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(IndexedFasta::new(reader, index))
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl IndexedFasta<BgzfReader<File>> {
+    /// Open a bgzipped FASTA file with its indices at `<path>.fai` and
+    /// `<path>.gzi`, building either index if its file doesn't exist
+    ///
+    /// # Errors
+    /// Fails if a file can't be read, the input isn't BGZF or an index can't
+    /// be built
+    pub fn open_bgzf<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut gzi_path = path.as_os_str().to_owned();
+        gzi_path.push(".gzi");
+        let mut fai_path = path.as_os_str().to_owned();
+        fai_path.push(".fai");
+
+        let gzi = match Gzi::from_path(&gzi_path) {
+            Ok(gzi) => gzi,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Gzi::build(BufReader::new(File::open(path)?))?
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut reader = BgzfReader::new(File::open(path)?).with_index(gzi);
+        let index = match Fai::from_path(&fai_path) {
+            Ok(index) => index,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let index = Fai::build(&mut reader)?;
+                reader.rewind()?;
+                index
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(IndexedFasta::new(reader, index))
+    }
+}
+
+impl<R: Read + Seek> IndexedFasta<R> {
+    pub fn new(reader: R, index: Fai) -> Self {
+        IndexedFasta {
+            reader,
+            index,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn index(&self) -> &Fai {
+        &self.index
+    }
+
+    /// Fetch the bases of `name` in the 0-based, half-open interval
+    /// `start..end`. The interval is clamped to the length of the sequence.
+    ///
+    /// # Errors
+    /// Fails if `name` is not in the index or on I/O errors
+    pub fn fetch(&mut self, name: &str, start: u64, end: u64) -> io::Result<Vec<u8>> {
+        let entry = self.index.get(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Sequence not in index: {name}"),
+            )
+        })?;
+
+        let end = end.min(entry.length);
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let from = entry.byte_offset(start);
+        let to = entry.byte_offset(end - 1) + 1;
+        let len = usize::try_from(to - from).map_err(invalid_data)?;
+
+        self.buffer.resize(len, 0);
+        self.reader.seek(SeekFrom::Start(from))?;
+        self.reader.read_exact(&mut self.buffer)?;
+
+        Ok(self
+            .buffer
+            .iter()
+            .copied()
+            .filter(|&b| b != b'\n' && b != b'\r')
+            .collect())
+    }
+
+    /// Fetch a region as a sequence type such as `Seq<Dna>`
+    ///
+    /// # Errors
+    /// Fails on I/O errors or if the bases can't be converted into `S`
+    pub fn fetch_seq<S, E>(&mut self, name: &str, start: u64, end: u64) -> io::Result<S>
+    where
+        S: for<'b> TryFrom<&'b [u8], Error = E>,
+        E: Into<ParseError>,
+    {
+        let bases = self.fetch(name, start, end)?;
+        S::try_from(&bases).map_err(|e| invalid_data(e.into()))
+    }
+
+    /// Fetch a samtools style region (`chr`, `chr:start` or `chr:start-end`,
+    /// 1-based and inclusive) as a record named after the region
+    ///
+    /// # Errors
+    /// Fails on malformed regions, unknown sequence names and I/O errors
+    pub fn region<S: TryFrom<Vec<u8>>>(&mut self, region: &str) -> io::Result<Record<Vec<u8>, S>> {
+        let (name, start, end) = self.parse_region(region)?;
+        let seq = self.fetch(&name, start, end)?;
+
+        Ok(Record {
+            raw_fields: region.as_bytes().to_vec(),
+            raw_seq: seq,
+            raw_quality: None,
+            _p: PhantomData,
+        })
+    }
+
+    fn parse_region(&self, region: &str) -> io::Result<(String, u64, u64)> {
+        // sequence names may themselves contain ':'
+        if self.index.get(region).is_some() {
+            return Ok((region.to_string(), 0, u64::MAX));
+        }
+
+        let bad_region = || invalid_data(format!("Invalid region: {region}"));
+        let (name, range) = region.rsplit_once(':').ok_or_else(bad_region)?;
+        let num = |s: &str| s.replace(',', "").parse::<u64>().map_err(|_| bad_region());
+
+        let (start, end) = match range.split_once('-') {
+            Some((start, "")) => (num(start)?, u64::MAX),
+            Some((start, end)) => (num(start)?, num(end)?),
+            None => (num(range)?, u64::MAX),
+        };
+
+        if start == 0 || start > end {
+            return Err(bad_region());
+        }
+
+        Ok((name.to_string(), start - 1, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bio_seq::prelude::*;
+    use std::io::Cursor;
+
+    const FA: &[u8] = b">chr1 first
+ACGTACGTAC
+GTACGTACGT
+ACG
+>chr2
+TTTTGGGGCC
+>chr3:x
+AAAA
+CC
+";
+
+    const FAI: &str = "chr1\t23\t12\t10\t11
+chr2\t10\t44\t10\t11
+chr3:x\t6\t63\t4\t5
+";
+
+    #[test]
+    fn test_build_fai() {
+        let fai = Fai::build(Cursor::new(FA)).unwrap();
+
+        let mut out = Vec::new();
+        fai.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), FAI);
+
+        assert_eq!(fai, Fai::read(Cursor::new(FAI)).unwrap());
+    }
+
+    #[test]
+    fn test_build_fai_crlf() {
+        let fa = b">chr1\r\nACGT\r\nACGT\r\nAC\r\n>chr2\r\nGG\r\n";
+        let fai = Fai::build(Cursor::new(fa)).unwrap();
+
+        assert_eq!(
+            fai.get("chr1").unwrap(),
+            &FaiRecord {
+                name: "chr1".to_string(),
+                length: 10,
+                offset: 7,
+                line_bases: 4,
+                line_width: 6,
+            }
+        );
+
+        let mut fasta = IndexedFasta::new(Cursor::new(fa), fai);
+        assert_eq!(fasta.fetch("chr1", 2, 9).unwrap(), b"GTACGTA");
+        assert_eq!(fasta.fetch("chr2", 0, 100).unwrap(), b"GG");
+    }
+
+    #[test]
+    fn test_inconsistent_lines() {
+        let fa = b">chr1\nACGT\nAC\nACGT\n";
+        assert!(Fai::build(Cursor::new(fa)).is_err());
+
+        let fa = b">chr1\nACGT\nACGTA\n";
+        assert!(Fai::build(Cursor::new(fa)).is_err());
+
+        // mixed line endings change the line width
+        let fa = b">chr1\nACGT\nACGT\r\nAC\n";
+        let e = Fai::build(Cursor::new(fa)).unwrap_err();
+        assert!(e.to_string().contains("Inconsistent line lengths"));
+    }
+
+    #[test]
+    fn test_invalid_fai() {
+        assert!(Fai::read(Cursor::new("chr1\t10\t6\t10\n")).is_err());
+        assert!(Fai::read(Cursor::new("chr1\t10\tX\t10\t11\n")).is_err());
+    }
+
+    #[test]
+    fn test_fetch() {
+        let fai = Fai::read(Cursor::new(FAI)).unwrap();
+        let mut fasta = IndexedFasta::new(Cursor::new(FA), fai);
+
+        assert_eq!(fasta.fetch("chr1", 0, 4).unwrap(), b"ACGT");
+        assert_eq!(fasta.fetch("chr1", 8, 13).unwrap(), b"ACGTA");
+        assert_eq!(fasta.fetch("chr1", 18, 100).unwrap(), b"GTACG");
+        assert_eq!(fasta.fetch("chr2", 4, 8).unwrap(), b"GGGG");
+        assert_eq!(fasta.fetch("chr2", 8, 4).unwrap(), b"");
+        assert!(fasta.fetch("chrX", 0, 4).is_err());
+
+        let seq: Seq<Dna> = fasta.fetch_seq("chr1", 8, 13).unwrap();
+        assert_eq!(seq, Seq::<Dna>::from_str("ACGTA").unwrap());
+    }
+
+    #[test]
+    fn test_region() {
+        let fai = Fai::read(Cursor::new(FAI)).unwrap();
+        let mut fasta = IndexedFasta::new(Cursor::new(FA), fai);
+
+        let record: Record<Vec<u8>> = fasta.region("chr1:9-13").unwrap();
+        assert_eq!(record.raw_fields, b"chr1:9-13");
+        assert_eq!(record.raw_seq, b"ACGTA");
+        assert!(record.raw_quality.is_none());
+
+        let record: Record<Vec<u8>> = fasta.region("chr1:1,1-2,0").unwrap();
+        assert_eq!(record.raw_seq, b"GTACGTACGT");
+
+        let record: Record<Vec<u8>> = fasta.region("chr2:7").unwrap();
+        assert_eq!(record.raw_seq, b"GGCC");
+
+        let record: Record<Vec<u8>> = fasta.region("chr3:x").unwrap();
+        assert_eq!(record.raw_seq, b"AAAACC");
+
+        let record: Record<Vec<u8>> = fasta.region("chr3:x:2-5").unwrap();
+        assert_eq!(record.raw_seq, b"AAAC");
+
+        assert!(fasta.region::<Vec<u8>>("chr1:0-4").is_err());
+        assert!(fasta.region::<Vec<u8>>("chr1:5-4").is_err());
+        assert!(fasta.region::<Vec<u8>>("chr1:a-b").is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_bgzf_fetch() {
+        use crate::bgzf::{BgzfWriter, Gzi};
+        use std::io::Write;
+
+        // flushing ends a block, so each line gets a block of its own
+        let mut writer = BgzfWriter::new(Vec::new());
+        for line in FA.split_inclusive(|&b| b == b'\n') {
+            writer.write_all(line).unwrap();
+            writer.flush().unwrap();
+        }
+        let gzi = writer.index().clone();
+        let bgzf = writer.finish().unwrap();
+        assert_eq!(gzi, Gzi::build(Cursor::new(&bgzf)).unwrap());
+
+        let mut reader = BgzfReader::new(Cursor::new(&bgzf)).with_index(gzi);
+        let fai = Fai::build(&mut reader).unwrap();
+        assert_eq!(fai, Fai::read(Cursor::new(FAI)).unwrap());
+        reader.rewind().unwrap();
+
+        let mut fasta = IndexedFasta::new(reader, fai);
+        assert_eq!(fasta.fetch("chr1", 8, 13).unwrap(), b"ACGTA");
+        assert_eq!(fasta.fetch("chr2", 4, 8).unwrap(), b"GGGG");
+        assert_eq!(fasta.fetch("chr1", 0, 4).unwrap(), b"ACGT");
+
+        let record: Record<Vec<u8>> = fasta.region("chr3:x:2-5").unwrap();
+        assert_eq!(record.raw_seq, b"AAAC");
+    }
+}
@@ -1,9 +1,420 @@
+use futures::io::AsyncBufRead;
+use futures::ready;
+use futures::Stream as AsyncIterator;
+use std::borrow::Cow;
+use std::io;
+use std::io::{BufRead, Read};
+use std::iter::Iterator;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+#[cfg(feature = "tokio")]
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use crate::compression;
+pub use crate::error::{Error, Format, ParseError, Position};
+use crate::fastq::poll_read_line;
+#[cfg(feature = "mmap")]
+use crate::mmap::MappedFasta;
+pub use crate::record::Record;
+use crate::scan;
+use crate::scan::trim_eol;
+
+pub struct FastaReader<R: BufRead + Unpin, S: TryFrom<Vec<u8>> = Vec<u8>> {
+    reader: Pin<Box<R>>,
+    line: Vec<u8>,
+    // header line of the next record, if it has already been read
+    header: Option<Vec<u8>>,
+    offset: usize,
+    record: usize,
+    line_no: usize,
+    _s: PhantomData<S>,
+}
+
+impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> FastaReader<R, S> {
+    pub fn new(reader: R) -> Self {
+        FastaReader {
+            reader: Box::pin(reader),
+            line: Vec::<u8>::with_capacity(1024),
+            header: None,
+            offset: 0,
+            record: 0,
+            line_no: 1,
+            _s: PhantomData,
+        }
+    }
+
+    fn location(&self) -> Position {
+        Position {
+            record: self.record,
+            line: self.line_no,
+            offset: self.offset,
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<usize> {
+        self.line.clear();
+        let n = self
+            .reader
+            .as_mut()
+            .get_mut()
+            .read_until(b'\n', &mut self.line)?;
+        self.offset += n;
+        self.line_no += 1;
+        Ok(n)
+    }
+
+    fn parse(&mut self) -> Option<Result<Record<Vec<u8>, S>, Error>> {
+        let header = if let Some(header) = self.header.take() {
+            header
+        } else {
+            // skip blank lines until the first header
+            loop {
+                let pos = self.location();
+                match self.read_line() {
+                    Ok(0) => return None,
+                    Ok(_) => {
+                        let line = trim_eol(&self.line);
+                        if line.is_empty() {
+                            continue;
+                        }
+                        if line[0] != b'>' {
+                            return Some(Err(Error::parse(
+                                Format::Fasta,
+                                ParseError::InvalidHeader(pos),
+                                Some(line),
+                            )));
+                        }
+                        break line[1..].to_vec();
+                    }
+                    Err(e) => return Some(Err(e.into())),
+                }
+            }
+        };
+
+        // join sequence lines until the next header or the end of the file
+        let mut seq = Vec::<u8>::with_capacity(self.line.capacity());
+        loop {
+            match self.read_line() {
+                Ok(0) => break,
+                Ok(_) => {
+                    let line = trim_eol(&self.line);
+                    if line.first() == Some(&b'>') {
+                        self.header = Some(line[1..].to_vec());
+                        break;
+                    }
+                    seq.extend_from_slice(line);
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+
+        self.record += 1;
+
+        Some(Ok(Record {
+            raw_fields: header,
+            raw_seq: seq,
+            raw_quality: None,
+            _p: PhantomData,
+        }))
+    }
+}
+
+impl<S: TryFrom<Vec<u8>>> FastaReader<Box<dyn BufRead + Send>, S> {
+    /// Open a file, detecting and decompressing gzip, bgzip, zstd, bzip2 or
+    /// xz input from its magic bytes
+    ///
+    /// # Errors
+    /// Fails if the file can't be opened or its compression format isn't
+    /// enabled
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(compression::open(path)?))
+    }
+
+    /// Read from a possibly compressed stream, see [`FastaReader::open`]
+    ///
+    /// # Errors
+    /// Fails if the magic bytes can't be read or the compression format
+    /// isn't enabled
+    pub fn from_reader<T: Read + Send + 'static>(reader: T) -> io::Result<Self> {
+        Ok(Self::new(compression::decompress(reader)?))
+    }
+}
+
+impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> Iterator for &mut FastaReader<R, S> {
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parse()
+    }
+}
+
+impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncIterator for FastaReader<R, S> {
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let record = unsafe { self.get_unchecked_mut().parse() };
+
+        Poll::Ready(record)
+    }
+}
+
+/// Non-blocking FASTA reader over an `AsyncBufRead`, yielding the same
+/// records as `FastaReader`. Partially read records are kept across polls.
+pub struct AsyncFastaReader<R: AsyncBufRead + Unpin, S: TryFrom<Vec<u8>> = Vec<u8>> {
+    reader: R,
+    line: Vec<u8>,
+    // header of the record being read
+    header: Option<Vec<u8>>,
+    seq: Vec<u8>,
+    offset: usize,
+    record: usize,
+    line_no: usize,
+    _s: PhantomData<S>,
+}
+
+impl<R: AsyncBufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncFastaReader<R, S> {
+    pub fn new(reader: R) -> Self {
+        AsyncFastaReader {
+            reader,
+            line: Vec::<u8>::with_capacity(1024),
+            header: None,
+            seq: Vec::new(),
+            offset: 0,
+            record: 0,
+            line_no: 1,
+            _s: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn location(&self) -> Position {
+        Position {
+            record: self.record,
+            line: self.line_no,
+            offset: self.offset,
+        }
+    }
+
+    fn finish_record(&mut self, header: Vec<u8>) -> Record<Vec<u8>, S> {
+        self.record += 1;
+        let capacity = self.seq.capacity();
+        Record {
+            raw_fields: header,
+            raw_seq: std::mem::replace(&mut self.seq, Vec::with_capacity(capacity)),
+            raw_quality: None,
+            _p: PhantomData,
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn poll_parse(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Record<Vec<u8>, S>, Error>>> {
+        loop {
+            let n = match ready!(poll_read_line(&mut self.reader, cx, &mut self.line, 0)) {
+                Ok(n) => n,
+                Err(e) => return Poll::Ready(Some(Err(e.into()))),
+            };
+
+            // end of file
+            if n == 0 {
+                return Poll::Ready(
+                    self.header
+                        .take()
+                        .map(|header| Ok(self.finish_record(header))),
+                );
+            }
+
+            let pos = self.location();
+            self.offset += n;
+            self.line_no += 1;
+
+            let line = trim_eol(&self.line);
+            let header = line.strip_prefix(b">").map(<[u8]>::to_vec);
+
+            if self.header.is_none() {
+                // skip blank lines until the first header
+                if header.is_none() && !line.is_empty() {
+                    let e = Error::parse(Format::Fasta, ParseError::InvalidHeader(pos), Some(line));
+                    self.line.clear();
+                    return Poll::Ready(Some(Err(e)));
+                }
+                self.header = header;
+            } else if let Some(header) = header {
+                let previous = self.header.replace(header).unwrap_or_default();
+                self.line.clear();
+                return Poll::Ready(Some(Ok(self.finish_record(previous))));
+            } else {
+                self.seq.extend_from_slice(line);
+            }
+
+            self.line.clear();
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncBufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncFastaReader<Compat<R>, S> {
+    /// Read from a `tokio` reader. Unbuffered readers can be wrapped in a
+    /// `tokio::io::BufReader`.
+    pub fn from_tokio(reader: R) -> Self {
+        Self::new(reader.compat())
+    }
+}
+
+impl<R: AsyncBufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncIterator for AsyncFastaReader<R, S> {
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        unsafe { self.get_unchecked_mut().poll_parse(cx) }
+    }
+}
+
+type CowRecord<'a, S> = Record<Cow<'a, [u8]>, S>;
+
+pub struct Fasta<'a, S: for<'b> TryFrom<&'b [u8]> = Vec<u8>> {
+    buffer: &'a [u8],
+    pos: usize,
+    record: usize,
+    line: usize,
+    _s: PhantomData<S>,
+}
+
+impl<'src, S: for<'b> TryFrom<&'b [u8]>> Fasta<'src, S> {
+    pub fn new(buf: &'src [u8]) -> Self {
+        Fasta {
+            buffer: buf,
+            pos: 0,
+            record: 0,
+            line: 1,
+            _s: PhantomData,
+        }
+    }
+
+    /// Memory map a file, or read it into memory if it can't be mapped,
+    /// for parsing with `MappedFasta::iter`
+    ///
+    /// # Errors
+    /// Fails if the file can't be opened, or can neither be mapped nor read
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> io::Result<MappedFasta<S>> {
+        MappedFasta::open(path)
+    }
+
+    fn location(&self) -> Position {
+        Position {
+            record: self.record,
+            line: self.line,
+            offset: self.pos,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<&'src [u8]> {
+        let buffer = self.buffer;
+        if self.pos >= buffer.len() {
+            return None;
+        }
+
+        self.line += 1;
+        let rest = &buffer[self.pos..];
+        let line = if let Some(n) = scan::find_newline(rest) {
+            self.pos += n + 1;
+            &rest[..=n]
+        } else {
+            // final line without a line terminator
+            self.pos = buffer.len();
+            rest
+        };
+
+        Some(trim_eol(line))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.buffer.get(self.pos).copied()
+    }
+
+    fn parse(&mut self) -> Option<Result<CowRecord<'src, S>, Error>> {
+        // skip blank lines until the first header
+        let header = loop {
+            let pos = self.location();
+            let line = self.next_line()?;
+            if line.is_empty() {
+                continue;
+            }
+            if line[0] != b'>' {
+                return Some(Err(Error::parse(
+                    Format::Fasta,
+                    ParseError::InvalidHeader(pos),
+                    Some(line),
+                )));
+            }
+            break &line[1..];
+        };
+
+        // borrow the sequence if it is on a single line, otherwise join the lines
+        let mut seq: Cow<'src, [u8]> = Cow::Borrowed(&[]);
+        while self.peek().is_some_and(|b| b != b'>') {
+            let line = self.next_line().unwrap_or_default();
+            if line.is_empty() {
+                continue;
+            }
+            if seq.is_empty() {
+                seq = Cow::Borrowed(line);
+            } else {
+                seq.to_mut().extend_from_slice(line);
+            }
+        }
+
+        self.record += 1;
+
+        Some(Ok(Record {
+            raw_fields: Cow::Borrowed(header),
+            raw_seq: seq,
+            raw_quality: None,
+            _p: PhantomData,
+        }))
+    }
+}
+
+impl<'a, S: for<'b> TryFrom<&'b [u8]>> Iterator for Fasta<'a, S> {
+    type Item = Result<Record<Cow<'a, [u8]>, S>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parse()
+    }
+}
+
+impl<'a, S: for<'b> TryFrom<&'b [u8]>> AsyncIterator for Fasta<'a, S> {
+    type Item = Result<Record<Cow<'a, [u8]>, S>, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let record = unsafe { self.get_unchecked_mut().parse() };
+
+        Poll::Ready(record)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use futures_test::task::noop_waker;
+    use bio_seq::prelude::*;
+    use futures::task::noop_waker;
     use std::io::Cursor;
-    use std::task::{Context, Poll};
+    use std::task::Context;
+
+    type DnaReader<'a> = FastaReader<Cursor<&'a [u8]>, Seq<Dna>>;
 
     #[test]
     fn test_fasta_iterator() {
@@ -14,23 +425,27 @@ CATCGACTACGGCG
 >SEQ_ID_2
 GGGGGGGGGGGGGG\n";
         let reader = Cursor::new(data as &[u8]);
-        let mut fasta: Fasta<Cursor<&[u8]>, Seq<Dna>> = Fasta::new(reader);
+        let mut fasta: DnaReader = FastaReader::new(reader);
 
-        let record1 = fasta.next().unwrap().unwrap();
-        assert_eq!(record1.fields, b"SEQ_ID_1".to_vec());
+        let record1 = (&mut fasta).next().unwrap().unwrap();
+        assert_eq!(record1.fields(), b"SEQ_ID_1");
         assert_eq!(
-            record1.seq,
-            dna!("ACTCGATCGCGACGACACGATCGCGCGCCATCGACTACGGCG")
+            record1.seq().unwrap(),
+            Seq::<Dna>::from_str("ACTCGATCGCGACGACACGATCGCGCGCCATCGACTACGGCG").unwrap()
         );
+        assert!(record1.raw_quality.is_none());
 
-        let record2 = fasta
+        let record2 = (&mut fasta)
             .next()
             .expect("Expected a record")
             .expect("Expected valid record");
-        assert_eq!(record2.fields, b"SEQ_ID_2".to_vec());
-        assert_eq!(record2.seq, dna!("GGGGGGGGGGGGGG"));
+        assert_eq!(record2.fields(), b"SEQ_ID_2");
+        assert_eq!(
+            record2.seq().unwrap(),
+            Seq::<Dna>::from_str("GGGGGGGGGGGGGG").unwrap()
+        );
 
-        assert!(fasta.next().is_none(), "Expected no more records");
+        assert!((&mut fasta).next().is_none(), "Expected no more records");
     }
 
     #[test]
@@ -43,8 +458,7 @@ GGGGGGGGGGGG
 TTTTTTTTTTT\n";
 
         let reader = Cursor::new(data as &[u8]);
-        let mut fasta: Pin<Box<Fasta<Cursor<&[u8]>, Seq<Dna>>>> =
-            Pin::new(Box::new(Fasta::new(reader)));
+        let mut fasta: Pin<Box<DnaReader>> = Pin::new(Box::new(FastaReader::new(reader)));
 
         let waker = noop_waker();
         let mut cx = Context::from_waker(&waker);
@@ -52,21 +466,30 @@ TTTTTTTTTTT\n";
         // Manual polling using poll_next
         match fasta.as_mut().poll_next(&mut cx) {
             Poll::Ready(Some(Ok(record))) => {
-                assert_eq!(record.fields, b"SEQ_ID_1".to_vec());
-                assert_eq!(record.seq, dna!("AAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGG"));
+                assert_eq!(record.fields(), b"SEQ_ID_1");
+                assert_eq!(
+                    record.seq().unwrap(),
+                    Seq::<Dna>::from_str("AAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGG").unwrap()
+                );
             }
             _ => panic!("Unexpected result"),
         }
 
         match fasta.as_mut().poll_next(&mut cx) {
             Poll::Ready(Some(Ok(record))) => {
-                assert_eq!(record.fields, b"SEQ_ID_2".to_vec());
-                assert_eq!(record.seq, dna!("TTTTTTTTTTT"));
+                assert_eq!(record.fields(), b"SEQ_ID_2");
+                assert_eq!(
+                    record.seq().unwrap(),
+                    Seq::<Dna>::from_str("TTTTTTTTTTT").unwrap()
+                );
             }
-            e => panic!("Unexpected result {:?}", e),
+            _ => panic!("Unexpected result"),
         }
 
-        assert_eq!(fasta.as_mut().poll_next(&mut cx), Poll::Ready(None));
+        assert!(matches!(
+            fasta.as_mut().poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
     }
 
     #[test]
@@ -74,8 +497,7 @@ TTTTTTTTTTT\n";
         let data = b">SEQ_ID_1\r\nAAAAAAAAAAAAAA\r\nCCCCCCCCCCCCC\r\nGGGGGGGGGGGG\r\n>SEQ_ID_2\r\nTTTTTTTTTTT";
 
         let reader = Cursor::new(data as &[u8]);
-        let mut fasta: Pin<Box<Fasta<Cursor<&[u8]>, Seq<Dna>>>> =
-            Pin::new(Box::new(Fasta::new(reader)));
+        let mut fasta: Pin<Box<DnaReader>> = Pin::new(Box::new(FastaReader::new(reader)));
 
         let waker = noop_waker();
         let mut cx = Context::from_waker(&waker);
@@ -83,21 +505,30 @@ TTTTTTTTTTT\n";
         // Manual polling using poll_next
         match fasta.as_mut().poll_next(&mut cx) {
             Poll::Ready(Some(Ok(record))) => {
-                assert_eq!(record.fields, b"SEQ_ID_1".to_vec());
-                assert_eq!(record.seq, dna!("AAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGG"));
+                assert_eq!(record.fields(), b"SEQ_ID_1");
+                assert_eq!(
+                    record.seq().unwrap(),
+                    Seq::<Dna>::from_str("AAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGG").unwrap()
+                );
             }
             _ => panic!("Unexpected result"),
         }
 
         match fasta.as_mut().poll_next(&mut cx) {
             Poll::Ready(Some(Ok(record))) => {
-                assert_eq!(record.fields, b"SEQ_ID_2".to_vec());
-                assert_eq!(record.seq, dna!("TTTTTTTTTTT"));
+                assert_eq!(record.fields(), b"SEQ_ID_2");
+                assert_eq!(
+                    record.seq().unwrap(),
+                    Seq::<Dna>::from_str("TTTTTTTTTTT").unwrap()
+                );
             }
-            e => panic!("Unexpected result {:?}", e),
+            _ => panic!("Unexpected result"),
         }
 
-        assert_eq!(fasta.as_mut().poll_next(&mut cx), Poll::Ready(None));
+        assert!(matches!(
+            fasta.as_mut().poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
     }
 
     #[test]
@@ -105,8 +536,7 @@ TTTTTTTTTTT\n";
         let data = b">SEQ_ID_X\nAAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGGACGTAAA";
 
         let reader = Cursor::new(data as &[u8]);
-        let mut fasta: Pin<Box<Fasta<Cursor<&[u8]>, Seq<Dna>>>> =
-            Pin::new(Box::new(Fasta::new(reader)));
+        let mut fasta: Pin<Box<DnaReader>> = Pin::new(Box::new(FastaReader::new(reader)));
 
         let waker = noop_waker();
         let mut cx = Context::from_waker(&waker);
@@ -114,15 +544,163 @@ TTTTTTTTTTT\n";
         // Manual polling using poll_next
         match fasta.as_mut().poll_next(&mut cx) {
             Poll::Ready(Some(Ok(record))) => {
-                assert_eq!(record.fields, b"SEQ_ID_X".to_vec());
+                assert_eq!(record.fields(), b"SEQ_ID_X");
                 assert_eq!(
-                    record.seq,
-                    dna!("AAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGGACGTAAA")
+                    record.seq().unwrap(),
+                    Seq::<Dna>::from_str("AAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGGACGTAAA").unwrap()
                 );
             }
             _ => panic!("Unexpected result"),
         }
 
-        assert_eq!(fasta.as_mut().poll_next(&mut cx), Poll::Ready(None));
+        assert!(matches!(
+            fasta.as_mut().poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+
+    #[test]
+    fn test_fasta_invalid_header() {
+        let reader = Cursor::new(b"\nACGT\n>SEQ_ID_1\nACGT\n" as &[u8]);
+        let mut fasta: FastaReader<Cursor<&[u8]>> = FastaReader::new(reader);
+
+        let e = (&mut fasta).next().unwrap().unwrap_err();
+        assert_eq!(e.format(), Some(Format::Fasta));
+        assert_eq!(
+            e.position(),
+            Some(Position {
+                record: 0,
+                line: 2,
+                offset: 1
+            })
+        );
+        assert_eq!(e.snippet(), Some(&b"ACGT"[..]));
+
+        let record = (&mut fasta).next().unwrap().unwrap();
+        assert_eq!(record.raw_fields, b"SEQ_ID_1");
+    }
+
+    const FA1: &[u8] = b">SEQ_ID_1 single line
+ACTCGATCGCGACG
+>SEQ_ID_2 wrapped
+AAAAAAAAAAAAAA
+CCCCCCCCCCCCC
+
+GGGGGGGGGGGG
+>SEQ_ID_3
+TTTTTTTTTTT";
+
+    #[test]
+    fn test_fasta_borrowed() {
+        let mut fasta: Fasta = Fasta::new(FA1);
+
+        let record1 = fasta.next().unwrap().unwrap();
+        assert_eq!(record1.fields(), b"SEQ_ID_1 single line");
+        assert!(matches!(record1.raw_seq, Cow::Borrowed(b"ACTCGATCGCGACG")));
+        assert!(record1.raw_quality.is_none());
+
+        let record2 = fasta.next().unwrap().unwrap();
+        assert_eq!(record2.fields(), b"SEQ_ID_2 wrapped");
+        assert!(matches!(record2.raw_seq, Cow::Owned(_)));
+        assert_eq!(
+            record2.seq().unwrap(),
+            b"AAAAAAAAAAAAAACCCCCCCCCCCCCGGGGGGGGGGGG"
+        );
+
+        let record3 = fasta.next().unwrap().unwrap();
+        assert_eq!(record3.fields(), b"SEQ_ID_3");
+        assert_eq!(record3.raw_seq.as_ref(), b"TTTTTTTTTTT");
+
+        assert!(fasta.next().is_none(), "Expected no more records");
+    }
+
+    #[test]
+    fn test_fasta_borrowed_crlf() {
+        let data = b">SEQ_ID_1\r\nAAAAAAAAAAAAAA\r\nCCCCCCCCCCCCC\r\n>SEQ_ID_2\r\nTTTTTTTTTTT\r\n";
+        let mut fasta: Fasta = Fasta::new(data);
+
+        let record1 = fasta.next().unwrap().unwrap();
+        assert_eq!(record1.fields(), b"SEQ_ID_1");
+        assert_eq!(record1.raw_seq.as_ref(), b"AAAAAAAAAAAAAACCCCCCCCCCCCC");
+
+        let record2 = fasta.next().unwrap().unwrap();
+        assert_eq!(record2.fields(), b"SEQ_ID_2");
+        assert!(matches!(record2.raw_seq, Cow::Borrowed(b"TTTTTTTTTTT")));
+
+        assert!(fasta.next().is_none(), "Expected no more records");
+    }
+
+    #[test]
+    fn test_fasta_borrowed_invalid_header() {
+        let mut fasta: Fasta = Fasta::new(b"ACGT\n>SEQ_ID_1\nACGT\n");
+        let e = fasta.next().unwrap().unwrap_err();
+        assert_eq!(
+            e.position(),
+            Some(Position {
+                record: 0,
+                line: 1,
+                offset: 0
+            })
+        );
+    }
+
+    const FA_MIXED: &[u8] =
+        b"\n>SEQ_ID_1 desc\r\nACTCGATCGC\r\nGACG\r\n\n>SEQ_ID_2\n>SEQ_ID_3\nGGGG\nnot a header";
+
+    #[test]
+    fn test_async_fasta_reader() {
+        use futures::executor::block_on;
+        use futures::io::BufReader;
+        use futures::StreamExt;
+
+        // a tiny buffer splits lines across reads
+        let reader = BufReader::with_capacity(3, futures::io::Cursor::new(FA_MIXED));
+        let records: Vec<_> = block_on(AsyncFastaReader::<_>::new(reader).collect());
+
+        let expected: Vec<_> = Iterator::collect(&mut FastaReader::<_>::new(Cursor::new(FA_MIXED)));
+        assert_eq!(records.len(), 3);
+        assert_eq!(records.len(), expected.len());
+        for (record, expected) in records.iter().zip(&expected) {
+            assert_eq!(record.as_ref().unwrap(), expected.as_ref().unwrap());
+        }
+
+        let reader = futures::io::Cursor::new(b"ACGT\n>SEQ_ID_1\nACGT");
+        let mut fasta: AsyncFastaReader<_> = AsyncFastaReader::new(reader);
+        let e = block_on(fasta.next()).unwrap().unwrap_err();
+        assert_eq!(e.position().unwrap().line, 1);
+        assert_eq!(e.snippet(), Some(&b"ACGT"[..]));
+        let record = block_on(fasta.next()).unwrap().unwrap();
+        assert_eq!(record.raw_seq, b"ACGT");
+        assert!(block_on(fasta.next()).is_none());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tokio_fasta_reader() {
+        use futures::StreamExt;
+        use tokio::io::AsyncWriteExt;
+
+        let (mut tx, rx) = tokio::io::duplex(16);
+        let write = async move {
+            tx.write_all(FA_MIXED).await.unwrap();
+            tx.shutdown().await.unwrap();
+        };
+
+        let fasta: AsyncFastaReader<_> =
+            AsyncFastaReader::from_tokio(tokio::io::BufReader::new(rx));
+        let (records, ()) = tokio::join!(fasta.collect::<Vec<_>>(), write);
+
+        let fields: Vec<_> = records
+            .into_iter()
+            .filter_map(|r| r.ok().map(|r| r.raw_fields))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                b"SEQ_ID_1 desc".to_vec(),
+                b"SEQ_ID_2".to_vec(),
+                b"SEQ_ID_3".to_vec()
+            ]
+        );
     }
 }
@@ -1,91 +1,194 @@
+use futures::io::AsyncBufRead;
+use futures::ready;
 use futures::Stream as AsyncIterator;
+use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Read};
 use std::iter::Iterator;
 use std::marker::PhantomData;
+use std::path::Path;
 use std::pin::Pin;
-use std::task::Poll;
+use std::task::{Context, Poll};
+#[cfg(feature = "tokio")]
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
 
-pub use crate::error::ParseError;
+use crate::compression;
+pub use crate::error::{Error, Format, ParseError, Position};
+#[cfg(feature = "mmap")]
+use crate::mmap::MappedFastq;
 pub use crate::record::{Phred, Record};
+use crate::scan;
+use crate::scan::trim_eol;
 
-fn build_record_borrowed<'a, S: TryFrom<&'a [u8]>>(
-    lines: &[&'a [u8]; 4],
-) -> Result<Record<&'a [u8], S>, io::Error> {
-    // test for valid header start
+/// How thoroughly FASTQ records are checked while parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Validation {
+    /// Only split the input into four line records
+    Lenient,
+    /// Check for the `@` header and `+` separator and that the sequence and
+    /// quality strings are the same length
+    #[default]
+    Standard,
+    /// Additionally check that a repeated id on the separator line matches the
+    /// header, that sequences only contain IUPAC nucleotide codes and that
+    /// qualities are in the Phred+33 range
+    Strict,
+}
 
-    if lines[0][0] != b'@' {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Invalid FASTQ header",
-        ));
+fn is_iupac(b: u8) -> bool {
+    matches!(
+        b.to_ascii_uppercase(),
+        b'A' | b'C'
+            | b'G'
+            | b'T'
+            | b'U'
+            | b'N'
+            | b'R'
+            | b'Y'
+            | b'K'
+            | b'M'
+            | b'S'
+            | b'W'
+            | b'B'
+            | b'D'
+            | b'H'
+            | b'V'
+    )
+}
+
+/// The positions of the start of each line of a four line record. `offsets`
+/// are the byte offsets of the lines and `pos` is the position of the header.
+fn line_starts(offsets: &[usize; 4], pos: Position) -> [Position; 4] {
+    std::array::from_fn(|i| Position {
+        record: pos.record,
+        line: pos.line + i,
+        offset: offsets[i],
+    })
+}
+
+/// Check the four lines of a record. `offsets` are the byte offsets of the
+/// start of each line and `pos` is the position of the header line.
+fn validate(
+    lines: &[&[u8]; 4],
+    offsets: &[usize; 4],
+    pos: Position,
+    level: Validation,
+) -> Result<(), ParseError> {
+    validate_sections(lines, &line_starts(offsets, pos), level)
+}
+
+/// Check the header, sequence, separator and quality of a record, where
+/// `starts` are the positions at which each of them begin
+fn validate_sections(
+    lines: &[&[u8]; 4],
+    starts: &[Position; 4],
+    level: Validation,
+) -> Result<(), ParseError> {
+    if level == Validation::Lenient {
+        return Ok(());
+    }
+
+    let at = |i: usize, col: usize| Position {
+        offset: starts[i].offset + col,
+        ..starts[i]
+    };
+
+    // test for valid header start
+    if lines[0].first() != Some(&b'@') {
+        return Err(ParseError::InvalidHeader(at(0, 0)));
     }
 
     // test for valid separator line
-    let sep_pos = lines[2][0];
-    if !sep_pos == b'+' {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Invalid FASTQ separator",
-        ));
+    if lines[2].first() != Some(&b'+') {
+        return Err(ParseError::InvalidSeparator(at(2, 0)));
     }
 
     // test that quality and sequence strings are equal length
     if lines[1].len() != lines[3].len() {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "FASTQ quality and sequence lines differ",
-        ));
+        return Err(ParseError::MismatchedLengths(at(3, 0)));
     }
 
-    Ok(Record {
-        raw_fields: &lines[0][1..],
-        raw_seq: lines[1],
-        raw_quality: Some(lines[3]),
-        _p: PhantomData,
-    })
-}
+    if level == Validation::Strict {
+        if lines[2].len() > 1 && lines[2][1..] != lines[0][1..] {
+            return Err(ParseError::MismatchedIds(at(2, 1)));
+        }
 
-fn build_record_owned<S: TryFrom<Vec<u8>>>(
-    lines: &[&[u8]; 4],
-) -> Result<Record<Vec<u8>, S>, io::Error> {
-    // test for valid header start
+        if let Some(col) = lines[1].iter().position(|&b| !is_iupac(b)) {
+            return Err(ParseError::InvalidBase(lines[1][col], at(1, col)));
+        }
 
-    if lines[0][0] != b'@' {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Invalid FASTQ header",
-        ));
+        if let Some(col) = lines[3].iter().position(|b| !(b'!'..=b'~').contains(b)) {
+            return Err(ParseError::InvalidQualityScore(lines[3][col], at(3, col)));
+        }
     }
 
-    // test for valid separator line
-    let sep_pos = lines[2][0];
-    if !sep_pos == b'+' {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Invalid FASTQ separator",
-        ));
-    }
+    Ok(())
+}
 
-    // test that quality and sequence strings are equal length
-    if lines[1].len() != lines[3].len() {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "FASTQ quality and sequence lines differ",
-        ));
+/// Attach the offending line of the record to a parse error
+fn record_error(e: ParseError, lines: &[&[u8]; 4], start: Position) -> Error {
+    let snippet = e
+        .position()
+        .and_then(|pos| lines.get(pos.line - start.line))
+        .copied();
+    Error::parse(Format::Fastq, e, snippet)
+}
+
+/// Attach the offending section of the record to a parse error
+fn section_error(e: ParseError, lines: &[&[u8]; 4], starts: &[Position; 4]) -> Error {
+    let snippet = e
+        .position()
+        .and_then(|pos| starts.iter().rposition(|s| s.line <= pos.line))
+        .map(|i| lines[i]);
+    Error::parse(Format::Fastq, e, snippet)
+}
+
+/// The four lines of a record in `buf`, given the end of each line,
+/// without their line terminators
+fn split_lines<'a>(buf: &'a [u8], ends: &[usize; 4]) -> [&'a [u8]; 4] {
+    [
+        trim_eol(&buf[0..ends[0]]),
+        trim_eol(&buf[ends[0]..ends[1]]),
+        trim_eol(&buf[ends[1]..ends[2]]),
+        trim_eol(&buf[ends[2]..ends[3]]),
+    ]
+}
+
+fn build_record_borrowed<'a, S>(lines: &[&'a [u8]; 4]) -> Record<&'a [u8], S> {
+    Record {
+        raw_fields: lines[0].strip_prefix(b"@").unwrap_or(lines[0]),
+        raw_seq: lines[1],
+        raw_quality: Some(lines[3]),
+        _p: PhantomData,
     }
+}
 
-    Ok(Record {
-        raw_fields: lines[0][1..].to_vec(),
+fn build_record_owned<S: TryFrom<Vec<u8>>>(lines: &[&[u8]; 4]) -> Record<Vec<u8>, S> {
+    Record {
+        raw_fields: lines[0].strip_prefix(b"@").unwrap_or(lines[0]).to_vec(),
         raw_seq: lines[1].to_vec(),
         raw_quality: Some(lines[3].to_vec()),
         _p: PhantomData,
-    })
+    }
+}
+
+/// Whether four lines look like the start of a FASTQ record, used to find the
+/// next record boundary after a malformed record
+pub(crate) fn is_record_start(lines: &[&[u8]; 4]) -> bool {
+    validate(lines, &[0; 4], Position::default(), Validation::Standard).is_ok()
 }
 
 pub struct Fastq<'a, S: TryFrom<&'a [u8]> = Vec<u8>> {
     buffer: &'a [u8],
     pos: usize,
+    record: usize,
+    line: usize,
+    validation: Validation,
+    recovery: bool,
+    // set after a malformed record when recovering
+    resync: bool,
+    skipped: usize,
     _s: PhantomData<S>,
 }
 
@@ -94,36 +197,306 @@ impl<'src, S: TryFrom<&'src [u8]>> Fastq<'src, S> {
         Fastq {
             buffer: buf,
             pos: 0,
+            record: 0,
+            line: 1,
+            validation: Validation::default(),
+            recovery: false,
+            resync: false,
+            skipped: 0,
             _s: PhantomData,
         }
     }
-    fn parse(&mut self) -> Option<Result<Record<&'src [u8], S>, std::io::Error>> {
-        if self.pos >= self.buffer.len() {
-            return None;
+
+    #[must_use]
+    pub fn validation(mut self, level: Validation) -> Self {
+        self.validation = level;
+        self
+    }
+
+    /// After reporting a malformed record, skip ahead to the next plausible
+    /// record instead of continuing from the line after it
+    #[must_use]
+    pub fn recovery(mut self, recover: bool) -> Self {
+        self.recovery = recover;
+        self
+    }
+
+    /// Number of bytes discarded while recovering from malformed records
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped
+    }
+
+    /// Memory map a file, or read it into memory if it can't be mapped,
+    /// for parsing with `MappedFastq::iter`
+    ///
+    /// # Errors
+    /// Fails if the file can't be opened, or can neither be mapped nor read
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> io::Result<MappedFastq<S>> {
+        MappedFastq::open(path)
+    }
+
+    /// Start parsing from a record boundary partway through the buffer
+    #[cfg(feature = "parallel")]
+    pub(crate) fn starting_at(mut self, pos: usize, record: usize, line: usize) -> Self {
+        self.pos = pos;
+        self.record = record;
+        self.line = line;
+        self
+    }
+
+    fn location(&self) -> Position {
+        Position {
+            record: self.record,
+            line: self.line,
+            offset: self.pos,
         }
+    }
 
+    /// Split the four lines of the record starting at `pos` and return them
+    /// with their offsets and the end of the record, or the number of
+    /// complete lines and the offset where the record is truncated. The
+    /// final line of the input needn't have a line terminator.
+    #[allow(clippy::type_complexity)]
+    fn split_record(
+        &self,
+        mut pos: usize,
+    ) -> Result<([&'src [u8]; 4], [usize; 4], usize), (usize, usize)> {
+        let buffer = self.buffer;
         let mut lines: [&[u8]; 4] = [&[]; 4];
+        let mut offsets: [usize; 4] = [0; 4];
 
-        for crs_i in &mut lines {
-            if let Some(n) = self.buffer[self.pos..].iter().position(|&b| b == b'\n') {
-                *crs_i = &self.buffer[self.pos..self.pos + n];
-                self.pos += n + 1;
+        for (i, (crs_i, offset)) in lines.iter_mut().zip(offsets.iter_mut()).enumerate() {
+            *offset = pos;
+            if let Some(n) = scan::find_newline(&buffer[pos..]) {
+                *crs_i = trim_eol(&buffer[pos..pos + n]);
+                pos += n + 1;
+            } else if pos < buffer.len() {
+                *crs_i = trim_eol(&buffer[pos..]);
+                pos = buffer.len();
             } else {
+                return Err((i, pos));
+            }
+        }
+
+        Ok((lines, offsets, pos))
+    }
+
+    /// Discard lines until the next plausible record
+    fn resync(&mut self) {
+        while self.pos < self.buffer.len() {
+            if let Ok((lines, ..)) = self.split_record(self.pos) {
+                if is_record_start(&lines) {
+                    return;
+                }
+            }
+
+            let n = scan::find_newline(&self.buffer[self.pos..])
+                .map_or(self.buffer.len() - self.pos, |n| n + 1);
+            self.pos += n;
+            self.line += 1;
+            self.skipped += n;
+        }
+    }
+
+    fn parse(&mut self) -> Option<Result<Record<&'src [u8], S>, Error>> {
+        if self.resync {
+            self.resync = false;
+            self.resync();
+        }
+
+        if self.pos >= self.buffer.len() {
+            return None;
+        }
+
+        let start = self.location();
+
+        let (lines, offsets, end) = match self.split_record(self.pos) {
+            Ok(record) => record,
+            Err((i, offset)) => {
                 // truncated records
-                return Some(Err(io::Error::new(
-                    io::ErrorKind::UnexpectedEof,
-                    "Truncated FASTQ record",
+                let pos = Position {
+                    record: self.record,
+                    line: self.line + i,
+                    offset,
+                };
+                self.pos = self.buffer.len();
+                return Some(Err(Error::parse(
+                    Format::Fastq,
+                    ParseError::Truncated(pos),
+                    None,
                 )));
             }
+        };
+
+        self.pos = end;
+        self.line += 4;
+        self.record += 1;
+
+        Some(
+            validate(&lines, &offsets, start, self.validation)
+                .map(|()| build_record_borrowed(&lines))
+                .map_err(|e| {
+                    if self.recovery {
+                        // rescan from the line after the bad record's header
+                        self.skipped += offsets[1] - offsets[0];
+                        self.pos = offsets[1];
+                        self.line = start.line + 1;
+                        self.resync = true;
+                    }
+                    record_error(e, &lines, start)
+                }),
+        )
+    }
+}
+
+impl<'src, S: for<'b> TryFrom<&'b [u8]>> Fastq<'src, S> {
+    /// Parse records whose sequence and quality strings may be wrapped over
+    /// several lines
+    pub fn multiline(self) -> MultilineFastq<'src, S> {
+        MultilineFastq {
+            buffer: self.buffer,
+            pos: self.pos,
+            record: self.record,
+            line: self.line,
+            validation: self.validation,
+            _s: PhantomData,
+        }
+    }
+}
+
+/// Append a line of a wrapped sequence or quality string, borrowing it if it
+/// is the first
+fn extend_section<'a>(section: &mut Cow<'a, [u8]>, line: &'a [u8]) {
+    if section.is_empty() {
+        *section = Cow::Borrowed(line);
+    } else {
+        section.to_mut().extend_from_slice(line);
+    }
+}
+
+/// Parser for in-memory FASTQ whose sequence and quality strings may be
+/// wrapped over several lines, as written by older Sanger tools
+///
+/// Sequence lines are read up to the `+` separator, then quality lines until
+/// they are as long as the sequence, so quality lines starting with `@` are
+/// handled. Records are borrowed from the buffer unless they are wrapped.
+/// Error positions within wrapped strings are counted from the start of the
+/// string, ignoring line breaks.
+pub struct MultilineFastq<'a, S: for<'b> TryFrom<&'b [u8]> = Vec<u8>> {
+    buffer: &'a [u8],
+    pos: usize,
+    record: usize,
+    line: usize,
+    validation: Validation,
+    _s: PhantomData<S>,
+}
+
+impl<'src, S: for<'b> TryFrom<&'b [u8]>> MultilineFastq<'src, S> {
+    pub fn new(buf: &'src [u8]) -> Self {
+        Fastq::new(buf).multiline()
+    }
+
+    #[must_use]
+    pub fn validation(mut self, level: Validation) -> Self {
+        self.validation = level;
+        self
+    }
+
+    fn location(&self) -> Position {
+        Position {
+            record: self.record,
+            line: self.line,
+            offset: self.pos,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<&'src [u8]> {
+        let buffer = self.buffer;
+        if self.pos >= buffer.len() {
+            return None;
+        }
+
+        self.line += 1;
+        let rest = &buffer[self.pos..];
+        if let Some(n) = scan::find_newline(rest) {
+            self.pos += n + 1;
+            Some(trim_eol(&rest[..n]))
+        } else {
+            // final line without a line terminator
+            self.pos = buffer.len();
+            Some(trim_eol(rest))
+        }
+    }
+
+    fn truncated(&self) -> Error {
+        Error::parse(Format::Fastq, ParseError::Truncated(self.location()), None)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse(&mut self) -> Option<Result<Record<Cow<'src, [u8]>, S>, Error>> {
+        let mut starts = [self.location(); 4];
+        let header = self.next_line()?;
+
+        starts[1] = self.location();
+        let mut seq: Cow<'src, [u8]> = Cow::Borrowed(&[]);
+        let separator = loop {
+            let pos = self.location();
+            let Some(line) = self.next_line() else {
+                return Some(Err(self.truncated()));
+            };
+            if line.first() == Some(&b'+') {
+                starts[2] = pos;
+                break line;
+            }
+            extend_section(&mut seq, line);
+        };
+
+        // at least one quality line, even for empty sequences
+        starts[3] = self.location();
+        let mut quality: Cow<'src, [u8]> = Cow::Borrowed(&[]);
+        loop {
+            let Some(line) = self.next_line() else {
+                return Some(Err(self.truncated()));
+            };
+            extend_section(&mut quality, line);
+            if quality.len() >= seq.len() {
+                break;
+            }
         }
 
-        Some(build_record_borrowed(&lines))
+        self.record += 1;
+
+        let lines = [header, &seq, separator, &quality];
+        if let Err(e) = validate_sections(&lines, &starts, self.validation) {
+            return Some(Err(section_error(e, &lines, &starts)));
+        }
+
+        Some(Ok(Record {
+            raw_fields: Cow::Borrowed(header.strip_prefix(b"@").unwrap_or(header)),
+            raw_seq: seq,
+            raw_quality: Some(quality),
+            _p: PhantomData,
+        }))
     }
 }
 
+/// Blocking FASTQ reader. Its `Stream` implementation reads synchronously,
+/// see `AsyncFastqReader` for non-blocking input.
 pub struct FastqReader<R: BufRead + Unpin, S: TryFrom<Vec<u8>> = Vec<u8>> {
     reader: Pin<Box<R>>,
     buffer: Vec<u8>,
+    offset: usize,
+    record: usize,
+    line: usize,
+    validation: Validation,
+    recovery: bool,
+    // set after a malformed record when recovering
+    resync: bool,
+    skipped: usize,
+    // lines read ahead of the parser while recovering
+    pending: VecDeque<Vec<u8>>,
+    multiline: bool,
     _s: PhantomData<S>,
 }
 
@@ -132,12 +505,200 @@ impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> FastqReader<R, S> {
         FastqReader {
             reader: Box::pin(reader),
             buffer: Vec::<u8>::with_capacity(1024),
+            offset: 0,
+            record: 0,
+            line: 1,
+            validation: Validation::default(),
+            recovery: false,
+            resync: false,
+            skipped: 0,
+            pending: VecDeque::new(),
+            multiline: false,
             _s: PhantomData,
         }
     }
 
-    fn parse(&mut self) -> Option<Result<Record<Vec<u8>, S>, std::io::Error>> {
+    #[must_use]
+    pub fn validation(mut self, level: Validation) -> Self {
+        self.validation = level;
+        self
+    }
+
+    /// After reporting a malformed record, skip ahead to the next plausible
+    /// record instead of continuing from the line after it
+    #[must_use]
+    pub fn recovery(mut self, recover: bool) -> Self {
+        self.recovery = recover;
+        self
+    }
+
+    /// Accept sequence and quality strings wrapped over several lines, as
+    /// parsed by `MultilineFastq`. A malformed record is always skipped as a
+    /// whole in this mode, so `recovery` has no effect.
+    #[must_use]
+    pub fn multiline(mut self, multiline: bool) -> Self {
+        self.multiline = multiline;
+        self
+    }
+
+    /// Number of bytes discarded while recovering from malformed records
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped
+    }
+
+    fn location(&self) -> Position {
+        Position {
+            record: self.record,
+            line: self.line,
+            offset: self.offset,
+        }
+    }
+
+    /// Append the next line to the buffer, taking lines that were read ahead
+    /// first
+    fn read_line(&mut self) -> io::Result<usize> {
+        if let Some(line) = self.pending.pop_front() {
+            self.buffer.extend_from_slice(&line);
+            return Ok(line.len());
+        }
+        self.reader
+            .as_mut()
+            .get_mut()
+            .read_until(b'\n', &mut self.buffer)
+    }
+
+    /// Discard lines until the next plausible record
+    fn resync(&mut self) -> io::Result<()> {
+        loop {
+            while self.pending.len() < 4 {
+                let mut line = Vec::new();
+                if self
+                    .reader
+                    .as_mut()
+                    .get_mut()
+                    .read_until(b'\n', &mut line)?
+                    == 0
+                {
+                    break;
+                }
+                self.pending.push_back(line);
+            }
+
+            if self.pending.len() == 4 {
+                let lines: [&[u8]; 4] = std::array::from_fn(|i| trim_eol(&self.pending[i]));
+                if is_record_start(&lines) {
+                    return Ok(());
+                }
+            }
+
+            let Some(line) = self.pending.pop_front() else {
+                return Ok(());
+            };
+            self.offset += line.len();
+            self.line += 1;
+            self.skipped += line.len();
+        }
+    }
+
+    /// Append the next line to the buffer without its line terminator.
+    /// Returns the number of bytes read.
+    fn read_unwrapped_line(&mut self) -> io::Result<usize> {
+        let start = self.buffer.len();
+        let n = self.read_line()?;
+        let len = trim_eol(&self.buffer[start..]).len();
+        self.buffer.truncate(start + len);
+        self.offset += n;
+        self.line += usize::from(n > 0);
+        Ok(n)
+    }
+
+    /// Read a record which may have wrapped sequence and quality strings into
+    /// the buffer as four lines and validate it, returning the ends of its
+    /// lines
+    fn read_multiline(&mut self) -> Option<Result<[usize; 4], Error>> {
+        let truncated = |reader: &Self| {
+            Error::parse(
+                Format::Fastq,
+                ParseError::Truncated(reader.location()),
+                None,
+            )
+        };
+
         self.buffer.clear();
+        let mut starts = [self.location(); 4];
+        let mut ends = [0; 4];
+
+        match self.read_unwrapped_line() {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(Err(e.into())),
+        }
+        self.buffer.push(b'\n');
+        ends[0] = self.buffer.len();
+
+        starts[1] = self.location();
+        loop {
+            let line_start = self.buffer.len();
+            let pos = self.location();
+            match self.read_unwrapped_line() {
+                Ok(0) => return Some(Err(truncated(self))),
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+            if self.buffer.get(line_start) == Some(&b'+') {
+                // end the sequence before the separator
+                self.buffer.insert(line_start, b'\n');
+                ends[1] = line_start + 1;
+                starts[2] = pos;
+                break;
+            }
+        }
+        self.buffer.push(b'\n');
+        ends[2] = self.buffer.len();
+
+        // at least one quality line, even for empty sequences
+        starts[3] = self.location();
+        let seq_len = ends[1] - 1 - ends[0];
+        loop {
+            match self.read_unwrapped_line() {
+                Ok(0) => return Some(Err(truncated(self))),
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+            if self.buffer.len() - ends[2] >= seq_len {
+                break;
+            }
+        }
+        self.buffer.push(b'\n');
+        ends[3] = self.buffer.len();
+
+        self.record += 1;
+
+        let lines = split_lines(&self.buffer, &ends);
+        if let Err(e) = validate_sections(&lines, &starts, self.validation) {
+            return Some(Err(section_error(e, &lines, &starts)));
+        }
+
+        Some(Ok(ends))
+    }
+
+    /// Read the next record into the buffer and validate it, returning the
+    /// ends of its lines
+    fn read_lines(&mut self) -> Option<Result<[usize; 4], Error>> {
+        if self.multiline {
+            return self.read_multiline();
+        }
+
+        if self.resync {
+            self.resync = false;
+            if let Err(e) = self.resync() {
+                return Some(Err(e.into()));
+            }
+        }
+
+        self.buffer.clear();
+
+        let start = self.location();
 
         // total bytes read
         let mut t_bs = 0;
@@ -145,10 +706,8 @@ impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> FastqReader<R, S> {
         // indices of carriage returns
         let mut crs: [usize; 4] = [0; 4];
 
-        let reader = self.reader.as_mut().get_mut();
-
-        for i in 0..4 {
-            match reader.read_until(b'\n', &mut self.buffer) {
+        for (i, cr) in crs.iter_mut().enumerate() {
+            match self.read_line() {
                 // end of file
                 Ok(0) => {
                     return if i == 0 {
@@ -156,35 +715,108 @@ impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> FastqReader<R, S> {
                         None
                     } else {
                         // truncated records
-                        return Some(Err(io::Error::new(
-                            io::ErrorKind::UnexpectedEof,
-                            "Truncated FASTQ record",
+                        return Some(Err(Error::parse(
+                            Format::Fastq,
+                            ParseError::Truncated(self.location()),
+                            None,
                         )));
                     };
                 }
                 Ok(n) => {
-                    crs[i] = n + t_bs;
+                    *cr = n + t_bs;
                     t_bs += n;
+                    self.offset += n;
+                    self.line += 1;
                 }
-                Err(e) => return Some(Err(e)),
+                Err(e) => return Some(Err(e.into())),
             }
         }
 
-        let buf = unsafe { std::slice::from_raw_parts(self.buffer.as_ptr(), self.buffer.len()) };
+        self.record += 1;
 
-        let lines: [&[u8]; 4] = [
-            &buf[0..crs[0] - 1],
-            &buf[crs[0]..crs[1] - 1],
-            &buf[crs[1]..crs[2] - 1],
-            &buf[crs[2]..crs[3] - 1],
+        let lines = split_lines(&self.buffer, &crs);
+
+        let offsets: [usize; 4] = [
+            start.offset,
+            start.offset + crs[0],
+            start.offset + crs[1],
+            start.offset + crs[2],
         ];
 
-        Some(build_record_owned(&lines))
+        if let Err(e) = validate(&lines, &offsets, start, self.validation) {
+            if self.recovery {
+                // rescan from the line after the bad record's header
+                for i in (1..4).rev() {
+                    self.pending
+                        .push_front(self.buffer[crs[i - 1]..crs[i]].to_vec());
+                }
+                self.skipped += crs[0];
+                self.offset = offsets[1];
+                self.line = start.line + 1;
+                self.resync = true;
+            }
+            return Some(Err(record_error(e, &lines, start)));
+        }
+
+        Some(Ok(crs))
+    }
+
+    fn parse(&mut self) -> Option<Result<Record<Vec<u8>, S>, Error>> {
+        Some(
+            self.read_lines()?
+                .map(|crs| build_record_owned(&split_lines(&self.buffer, &crs))),
+        )
+    }
+
+    /// Read the next record into `record`, reusing its allocations. Returns
+    /// `false` at the end of the input.
+    ///
+    /// # Errors
+    /// Fails on I/O errors and malformed records. `record` is left unchanged
+    /// on error.
+    pub fn read_record(&mut self, record: &mut Record<Vec<u8>, S>) -> Result<bool, Error> {
+        let Some(crs) = self.read_lines().transpose()? else {
+            return Ok(false);
+        };
+        let lines = split_lines(&self.buffer, &crs);
+
+        record.raw_fields.clear();
+        record
+            .raw_fields
+            .extend_from_slice(lines[0].strip_prefix(b"@").unwrap_or(lines[0]));
+        record.raw_seq.clear();
+        record.raw_seq.extend_from_slice(lines[1]);
+        let quality = record.raw_quality.get_or_insert_with(Vec::new);
+        quality.clear();
+        quality.extend_from_slice(lines[3]);
+
+        Ok(true)
+    }
+}
+
+impl<S: TryFrom<Vec<u8>>> FastqReader<Box<dyn BufRead + Send>, S> {
+    /// Open a file, detecting and decompressing gzip, bgzip, zstd, bzip2 or
+    /// xz input from its magic bytes
+    ///
+    /// # Errors
+    /// Fails if the file can't be opened or its compression format isn't
+    /// enabled
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(compression::open(path)?))
+    }
+
+    /// Read from a possibly compressed stream, see [`FastqReader::open`]
+    ///
+    /// # Errors
+    /// Fails if the magic bytes can't be read or the compression format
+    /// isn't enabled
+    pub fn from_reader<T: Read + Send + 'static>(reader: T) -> io::Result<Self> {
+        Ok(Self::new(compression::decompress(reader)?))
     }
 }
 
 impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> Iterator for &mut FastqReader<R, S> {
-    type Item = Result<Record<Vec<u8>, S>, std::io::Error>;
+    type Item = Result<Record<Vec<u8>, S>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.parse()
@@ -192,7 +824,7 @@ impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> Iterator for &mut FastqReader<R, S
 }
 
 impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncIterator for FastqReader<R, S> {
-    type Item = Result<Record<Vec<u8>, S>, std::io::Error>;
+    type Item = Result<Record<Vec<u8>, S>, Error>;
 
     fn poll_next(
         self: Pin<&mut Self>,
@@ -204,8 +836,472 @@ impl<R: BufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncIterator for FastqReader<R, S
     }
 }
 
+// records located per scan of the buffer
+const RECORD_BATCH: usize = 256;
+
+/// FASTQ reader which lends out records borrowed from the buffer of a
+/// `BufRead`, such as a `BufReader` or a decoder from `compression::open`.
+/// Whole records are parsed in place, and only a record split across two
+/// fills of the reader's buffer is copied, so that it can be completed with
+/// the lines at the start of the next fill.
+pub struct SlidingFastqReader<R: BufRead, S = Vec<u8>> {
+    reader: R,
+    // a record split across fills of the reader's buffer
+    carry: Vec<u8>,
+    // bytes parsed from the front of the current window, which is `carry`
+    // if it isn't empty and otherwise the reader's buffer
+    start: usize,
+    eof: bool,
+    // set once a newline has been added after an unterminated final line
+    terminated: bool,
+    // offset in the input of the start of the current window
+    consumed: usize,
+    // newlines of the records found by the last scan, and the next to parse
+    ends: Vec<[usize; 4]>,
+    next: usize,
+    record: usize,
+    line: usize,
+    validation: Validation,
+    _s: PhantomData<S>,
+}
+
+impl<R: BufRead, S> SlidingFastqReader<R, S> {
+    pub fn new(reader: R) -> Self {
+        SlidingFastqReader {
+            reader,
+            carry: Vec::new(),
+            start: 0,
+            eof: false,
+            terminated: false,
+            consumed: 0,
+            ends: Vec::with_capacity(RECORD_BATCH),
+            next: 0,
+            record: 0,
+            line: 1,
+            validation: Validation::default(),
+            _s: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn validation(mut self, level: Validation) -> Self {
+        self.validation = level;
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Find the next batch of records in the reader's buffer. Once it holds
+    /// no complete records it is consumed, with a trailing partial record
+    /// copied to `carry`.
+    fn scan_buffer(&mut self) -> io::Result<()> {
+        let buf = self.reader.fill_buf()?;
+        if self.start == buf.len() {
+            self.eof = buf.is_empty();
+        } else if scan::fastq_records(buf, self.start, RECORD_BATCH, &mut self.ends) > self.start {
+            return Ok(());
+        } else {
+            self.carry.extend_from_slice(&buf[self.start..]);
+        }
+
+        let n = buf.len();
+        self.reader.consume(n);
+        self.consumed += self.start;
+        if self.carry.is_empty() {
+            self.consumed += n - self.start;
+        }
+        self.start = 0;
+        Ok(())
+    }
+
+    /// Append lines from the reader to the partial record in `carry`, up to
+    /// the end of the record if the reader's buffer holds it
+    fn extend_carry(&mut self) -> io::Result<()> {
+        let lines = scan::count_newlines(&self.carry[self.start..]);
+        let buf = self.reader.fill_buf()?;
+        if buf.is_empty() {
+            self.eof = true;
+            return Ok(());
+        }
+
+        let mut n = 0;
+        for _ in lines..4 {
+            let Some(i) = scan::find_newline(&buf[n..]) else {
+                n = buf.len();
+                break;
+            };
+            n += i + 1;
+        }
+        self.carry.extend_from_slice(&buf[..n]);
+        self.reader.consume(n);
+        Ok(())
+    }
+
+    /// The number of complete lines of a truncated final record, and the
+    /// offset of its incomplete line
+    fn truncated_lines(&self) -> (usize, usize) {
+        let mut lines = 0;
+        let mut pos = self.start;
+        while let Some(n) = scan::find_newline(&self.carry[pos..]) {
+            lines += 1;
+            pos += n + 1;
+        }
+        // not counting an added newline
+        (
+            lines,
+            pos.min(self.carry.len() - usize::from(self.terminated)),
+        )
+    }
+
+    /// The next record, borrowed from the reader's buffer
+    pub fn next_record(&mut self) -> Option<Result<Record<&[u8], S>, Error>> {
+        let newlines = loop {
+            if let Some(&newlines) = self.ends.get(self.next) {
+                self.next += 1;
+                break newlines;
+            }
+            self.ends.clear();
+            self.next = 0;
+
+            if self.carry.is_empty() {
+                if self.eof {
+                    return None;
+                }
+                if let Err(e) = self.scan_buffer() {
+                    return Some(Err(e.into()));
+                }
+                continue;
+            }
+
+            // the carried record has been parsed
+            if self.start == self.carry.len() {
+                self.consumed += self.carry.len();
+                self.carry.clear();
+                self.start = 0;
+                continue;
+            }
+
+            scan::fastq_records(&self.carry, self.start, 1, &mut self.ends);
+            if !self.ends.is_empty() {
+                continue;
+            }
+
+            if !self.eof {
+                if let Err(e) = self.extend_carry() {
+                    return Some(Err(e.into()));
+                }
+                continue;
+            }
+
+            // terminate a final line without a newline and scan again
+            if self.carry.last() != Some(&b'\n') {
+                self.carry.push(b'\n');
+                self.terminated = true;
+                continue;
+            }
+
+            // truncated records
+            let (i, offset) = self.truncated_lines();
+            let pos = Position {
+                record: self.record,
+                line: self.line + i,
+                offset: self.consumed + offset,
+            };
+            self.start = self.carry.len();
+            return Some(Err(Error::parse(
+                Format::Fastq,
+                ParseError::Truncated(pos),
+                None,
+            )));
+        };
+
+        let starts = [
+            self.start,
+            newlines[0] + 1,
+            newlines[1] + 1,
+            newlines[2] + 1,
+        ];
+        let start = Position {
+            record: self.record,
+            line: self.line,
+            offset: self.consumed + self.start,
+        };
+
+        self.start = newlines[3] + 1;
+        self.record += 1;
+        self.line += 4;
+
+        // the reader's buffer is unchanged until it is consumed
+        let buffer: &[u8] = if self.carry.is_empty() {
+            match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(e) => return Some(Err(e.into())),
+            }
+        } else {
+            &self.carry
+        };
+        let lines: [&[u8]; 4] = std::array::from_fn(|i| trim_eol(&buffer[starts[i]..newlines[i]]));
+        let offsets = starts.map(|s| s + self.consumed);
+
+        Some(
+            validate(&lines, &offsets, start, self.validation)
+                .map(|()| build_record_borrowed(&lines))
+                .map_err(|e| record_error(e, &lines, start)),
+        )
+    }
+}
+
+/// Append the rest of the current line to `buf`, including its newline.
+/// Partial lines are kept in `buf` across `Poll::Pending`, so `start` is the
+/// offset in `buf` where the line began. Returns the length of the line,
+/// which is 0 at the end of the input.
+pub(crate) fn poll_read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    cx: &mut Context<'_>,
+    buf: &mut Vec<u8>,
+    start: usize,
+) -> Poll<io::Result<usize>> {
+    loop {
+        let available = ready!(Pin::new(&mut *reader).poll_fill_buf(cx))?;
+        if available.is_empty() {
+            return Poll::Ready(Ok(buf.len() - start));
+        }
+
+        if let Some(i) = scan::find_newline(available) {
+            buf.extend_from_slice(&available[..=i]);
+            Pin::new(&mut *reader).consume(i + 1);
+            return Poll::Ready(Ok(buf.len() - start));
+        }
+
+        let n = available.len();
+        buf.extend_from_slice(available);
+        Pin::new(&mut *reader).consume(n);
+    }
+}
+
+/// Non-blocking FASTQ reader over an `AsyncBufRead`, yielding the same
+/// records as `FastqReader`. Partially read records are kept across polls.
+pub struct AsyncFastqReader<R: AsyncBufRead + Unpin, S: TryFrom<Vec<u8>> = Vec<u8>> {
+    reader: R,
+    buffer: Vec<u8>,
+    // ends of the lines of the current record read so far
+    ends: [usize; 4],
+    lines: usize,
+    offset: usize,
+    record: usize,
+    line: usize,
+    validation: Validation,
+    recovery: bool,
+    // set after a malformed record when recovering
+    resync: bool,
+    skipped: usize,
+    // lines read ahead of the parser while recovering
+    pending: VecDeque<Vec<u8>>,
+    lookahead: Vec<u8>,
+    _s: PhantomData<S>,
+}
+
+impl<R: AsyncBufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncFastqReader<R, S> {
+    pub fn new(reader: R) -> Self {
+        AsyncFastqReader {
+            reader,
+            buffer: Vec::<u8>::with_capacity(1024),
+            ends: [0; 4],
+            lines: 0,
+            offset: 0,
+            record: 0,
+            line: 1,
+            validation: Validation::default(),
+            recovery: false,
+            resync: false,
+            skipped: 0,
+            pending: VecDeque::new(),
+            lookahead: Vec::new(),
+            _s: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn validation(mut self, level: Validation) -> Self {
+        self.validation = level;
+        self
+    }
+
+    /// After reporting a malformed record, skip ahead to the next plausible
+    /// record instead of continuing from the line after it
+    #[must_use]
+    pub fn recovery(mut self, recover: bool) -> Self {
+        self.recovery = recover;
+        self
+    }
+
+    /// Number of bytes discarded while recovering from malformed records
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn location(&self) -> Position {
+        Position {
+            record: self.record,
+            line: self.line,
+            offset: self.offset,
+        }
+    }
+
+    /// Discard lines until the next plausible record
+    fn poll_resync(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            while self.pending.len() < 4 {
+                if ready!(poll_read_line(&mut self.reader, cx, &mut self.lookahead, 0))? == 0 {
+                    break;
+                }
+                self.pending.push_back(std::mem::take(&mut self.lookahead));
+            }
+
+            if self.pending.len() == 4 {
+                let lines: [&[u8]; 4] = std::array::from_fn(|i| trim_eol(&self.pending[i]));
+                if is_record_start(&lines) {
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            let Some(line) = self.pending.pop_front() else {
+                return Poll::Ready(Ok(()));
+            };
+            self.offset += line.len();
+            self.line += 1;
+            self.skipped += line.len();
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn poll_parse(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Record<Vec<u8>, S>, Error>>> {
+        if self.resync {
+            if let Err(e) = ready!(self.poll_resync(cx)) {
+                self.resync = false;
+                return Poll::Ready(Some(Err(e.into())));
+            }
+            self.resync = false;
+        }
+
+        while self.lines < 4 {
+            let start = self.lines.checked_sub(1).map_or(0, |i| self.ends[i]);
+
+            // take lines that were read ahead first, unless a line is partly read
+            let pending = if self.buffer.len() == start {
+                self.pending.pop_front()
+            } else {
+                None
+            };
+
+            let n = match pending {
+                Some(line) => {
+                    self.buffer.extend_from_slice(&line);
+                    line.len()
+                }
+                None => match ready!(poll_read_line(
+                    &mut self.reader,
+                    cx,
+                    &mut self.buffer,
+                    start
+                )) {
+                    Ok(n) => n,
+                    Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                },
+            };
+
+            if n == 0 {
+                // end of file
+                let lines = self.lines;
+                self.buffer.clear();
+                self.lines = 0;
+                return Poll::Ready(if lines == 0 {
+                    None
+                } else {
+                    // truncated records
+                    Some(Err(Error::parse(
+                        Format::Fastq,
+                        ParseError::Truncated(self.location()),
+                        None,
+                    )))
+                });
+            }
+
+            self.ends[self.lines] = start + n;
+            self.lines += 1;
+            self.offset += n;
+            self.line += 1;
+        }
+
+        let ends = self.ends;
+        let start = Position {
+            record: self.record,
+            line: self.line - 4,
+            offset: self.offset - ends[3],
+        };
+        self.record += 1;
+        self.lines = 0;
+
+        let buf = std::mem::take(&mut self.buffer);
+        let lines = split_lines(&buf, &ends);
+        let offsets: [usize; 4] = [
+            start.offset,
+            start.offset + ends[0],
+            start.offset + ends[1],
+            start.offset + ends[2],
+        ];
+
+        let record = validate(&lines, &offsets, start, self.validation)
+            .map(|()| build_record_owned(&lines))
+            .map_err(|e| {
+                if self.recovery {
+                    // rescan from the line after the bad record's header
+                    for i in (1..4).rev() {
+                        self.pending.push_front(buf[ends[i - 1]..ends[i]].to_vec());
+                    }
+                    self.skipped += ends[0];
+                    self.offset = offsets[1];
+                    self.line = start.line + 1;
+                    self.resync = true;
+                }
+                record_error(e, &lines, start)
+            });
+
+        // reuse the allocation for the next record
+        self.buffer = buf;
+        self.buffer.clear();
+        Poll::Ready(Some(record))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncBufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncFastqReader<Compat<R>, S> {
+    /// Read from a `tokio` reader. Unbuffered readers can be wrapped in a
+    /// `tokio::io::BufReader`.
+    pub fn from_tokio(reader: R) -> Self {
+        Self::new(reader.compat())
+    }
+}
+
+impl<R: AsyncBufRead + Unpin, S: TryFrom<Vec<u8>>> AsyncIterator for AsyncFastqReader<R, S> {
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        unsafe { self.get_unchecked_mut().poll_parse(cx) }
+    }
+}
+
 impl<'a, S: TryFrom<&'a [u8]>> Iterator for Fastq<'a, S> {
-    type Item = Result<Record<&'a [u8], S>, std::io::Error>;
+    type Item = Result<Record<&'a [u8], S>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.parse()
@@ -213,7 +1309,28 @@ impl<'a, S: TryFrom<&'a [u8]>> Iterator for Fastq<'a, S> {
 }
 
 impl<'a, S: for<'b> TryFrom<&'b [u8]>> AsyncIterator for Fastq<'a, S> {
-    type Item = Result<Record<&'a [u8], S>, std::io::Error>;
+    type Item = Result<Record<&'a [u8], S>, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let record = unsafe { self.get_unchecked_mut().parse() };
+
+        Poll::Ready(record)
+    }
+}
+
+impl<'a, S: for<'b> TryFrom<&'b [u8]>> Iterator for MultilineFastq<'a, S> {
+    type Item = Result<Record<Cow<'a, [u8]>, S>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parse()
+    }
+}
+
+impl<'a, S: for<'b> TryFrom<&'b [u8]>> AsyncIterator for MultilineFastq<'a, S> {
+    type Item = Result<Record<Cow<'a, [u8]>, S>, Error>;
 
     fn poll_next(
         self: Pin<&mut Self>,
@@ -230,11 +1347,11 @@ mod tests {
     use super::*;
     use futures::task::noop_waker;
     //    use futures::Stream;
-    use std::io::Cursor;
+    use std::io::{BufReader, Cursor};
     use std::iter::Iterator;
     use std::task::Context;
 
-    const FQ1: &'static [u8] = b"@SEQ_ID_1
+    const FQ1: &[u8] = b"@SEQ_ID_1
 ACTCGATCGCGACGAA
 +
 AFFFFFFFFFFFFEBA
@@ -291,4 +1408,666 @@ GGGGGGGGGGGGGG\n";
 
         //assert_eq!(fastq.as_mut().poll_next(&mut cx), Poll::Ready(None));
     }
+
+    fn parse_error(e: &Error) -> &ParseError {
+        e.parse_error().unwrap()
+    }
+
+    #[test]
+    fn test_fastq_validation_levels() {
+        let fq = b"@SEQ_ID_1\nACGT\n-\nIIII\n";
+
+        let mut fastq: Fastq = Fastq::new(fq).validation(Validation::Lenient);
+        assert!(fastq.next().unwrap().is_ok());
+
+        let mut fastq: Fastq = Fastq::new(fq);
+        let e = fastq.next().unwrap().unwrap_err();
+        assert!(matches!(
+            parse_error(&e),
+            ParseError::InvalidSeparator(Position {
+                record: 0,
+                line: 3,
+                offset: 15
+            })
+        ));
+
+        let fq = b"@SEQ_ID_1\nACGT\n+\nIIII\n@SEQ_ID_2\nACGX\n+SEQ_ID_2\nII#I\n";
+
+        let mut fastq: Fastq = Fastq::new(fq);
+        assert!(fastq.next().unwrap().is_ok());
+        assert!(fastq.next().unwrap().is_ok());
+
+        let mut fastq: Fastq = Fastq::new(fq).validation(Validation::Strict);
+        assert!(fastq.next().unwrap().is_ok());
+        let e = fastq.next().unwrap().unwrap_err();
+        assert!(matches!(
+            parse_error(&e),
+            ParseError::InvalidBase(
+                b'X',
+                Position {
+                    record: 1,
+                    line: 6,
+                    offset: 35
+                }
+            )
+        ));
+        assert!(fastq.next().is_none());
+    }
+
+    #[test]
+    fn test_fastq_strict_errors() {
+        type Check = fn(&ParseError) -> bool;
+
+        let cases: [(&[u8], Check); 5] = [
+            (b"\nACGT\n+\nIIII\n", |e| {
+                matches!(e, ParseError::InvalidHeader(Position { line: 1, .. }))
+            }),
+            (b"@SEQ_ID_1\nACGT\n+\nIII\n", |e| {
+                matches!(e, ParseError::MismatchedLengths(Position { line: 4, .. }))
+            }),
+            (b"@SEQ_ID_1\nACGT\n+SEQ_ID_2\nIIII\n", |e| {
+                matches!(e, ParseError::MismatchedIds(Position { offset: 16, .. }))
+            }),
+            (b"@SEQ_ID_1\nACGT\n+\nII I\n", |e| {
+                matches!(
+                    e,
+                    ParseError::InvalidQualityScore(b' ', Position { offset: 19, .. })
+                )
+            }),
+            (b"@SEQ_ID_1\nACGT\n+\n", |e| {
+                matches!(e, ParseError::Truncated(Position { line: 4, .. }))
+            }),
+        ];
+
+        for (fq, is_expected) in cases {
+            let mut fastq: Fastq = Fastq::new(fq).validation(Validation::Strict);
+            let e = fastq.next().unwrap().unwrap_err();
+            assert!(is_expected(parse_error(&e)), "{e}");
+
+            let mut reader: FastqReader<Cursor<&[u8]>> =
+                FastqReader::new(Cursor::new(fq)).validation(Validation::Strict);
+            let e = (&mut reader).next().unwrap().unwrap_err();
+            assert!(is_expected(parse_error(&e)), "{e}");
+        }
+    }
+
+    #[test]
+    fn test_fastq_reader_positions() {
+        let fq = b"@SEQ_ID_1\nACGT\n+\nIIII\n@SEQ_ID_2\nACGT\n+\nIII\n";
+        let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(fq));
+
+        assert!((&mut reader).next().unwrap().is_ok());
+        let e = (&mut reader).next().unwrap().unwrap_err();
+        assert_eq!(
+            parse_error(&e).position(),
+            Some(Position {
+                record: 1,
+                line: 8,
+                offset: 39
+            })
+        );
+    }
+
+    fn count_records(fq: &[u8]) -> std::io::Result<usize> {
+        let mut n = 0;
+        let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(fq));
+        for record in &mut reader {
+            record?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    #[test]
+    fn test_fastq_error_context() {
+        let fq = b"@SEQ_ID_1\nACGT\n+\nIIII\nSEQ_ID_2\nACGT\n+\nIIII\n";
+        let mut fastq: Fastq = Fastq::new(fq);
+
+        assert!(fastq.next().unwrap().is_ok());
+        let e = fastq.next().unwrap().unwrap_err();
+        assert_eq!(e.format(), Some(Format::Fastq));
+        assert_eq!(e.snippet(), Some(&b"SEQ_ID_2"[..]));
+        assert_eq!(
+            e.to_string(),
+            "FASTQ parse error: Invalid header at record 1, line 5, byte 22: \"SEQ_ID_2\""
+        );
+
+        // parse errors convert into `io::Error`s
+
+        let e = count_records(fq).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(count_records(&fq[..22]).unwrap(), 1);
+        assert_eq!(
+            count_records(&fq[..30]).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    const FQ_CORRUPT: &[u8] = b"@r1\nACGT\n+\nIIII\n@r2\nACGTAC\n@r3\nACGT\n+\nIIII\ngarbage line\n@r4\nAC\n+\nII\n@r5\nAC";
+
+    #[test]
+    fn test_fastq_no_recovery() {
+        let fastq: Fastq = Fastq::new(FQ_CORRUPT);
+        let results: Vec<bool> = fastq.map(|r| r.is_ok()).collect();
+
+        assert_eq!(results, [true, false, false, false, false]);
+    }
+
+    #[test]
+    fn test_fastq_recovery() {
+        let mut fastq: Fastq = Fastq::new(FQ_CORRUPT).recovery(true);
+
+        assert_eq!(fastq.next().unwrap().unwrap().fields(), b"r1");
+        let e = fastq.next().unwrap().unwrap_err();
+        assert!(matches!(
+            e.parse_error(),
+            Some(ParseError::InvalidSeparator(_))
+        ));
+        assert_eq!(fastq.next().unwrap().unwrap().fields(), b"r3");
+        assert!(fastq.next().unwrap().is_err());
+        assert_eq!(fastq.next().unwrap().unwrap().fields(), b"r4");
+
+        // truncated records are still reported
+        let e = fastq.next().unwrap().unwrap_err();
+        assert!(matches!(e.parse_error(), Some(ParseError::Truncated(_))));
+        assert!(fastq.next().is_none());
+
+        // "@r2\nACGTAC\n" and "garbage line\n"
+        assert_eq!(fastq.skipped_bytes(), 11 + 13);
+    }
+
+    #[test]
+    fn test_fastq_reader_recovery() {
+        let mut reader: FastqReader<Cursor<&[u8]>> =
+            FastqReader::new(Cursor::new(FQ_CORRUPT)).recovery(true);
+
+        let mut fields = Vec::new();
+        let mut errors = Vec::new();
+        for record in &mut reader {
+            match record {
+                Ok(record) => fields.push(record.raw_fields),
+                Err(e) => errors.push(e.position().unwrap()),
+            }
+        }
+
+        assert_eq!(fields, [b"r1".to_vec(), b"r3".to_vec(), b"r4".to_vec()]);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[..2],
+            [
+                Position {
+                    record: 1,
+                    line: 7,
+                    offset: 27
+                },
+                Position {
+                    record: 3,
+                    line: 11,
+                    offset: 43
+                }
+            ]
+        );
+        assert_eq!(reader.skipped_bytes(), 11 + 13);
+    }
+
+    /// Async input that delivers a few bytes at a time, returning
+    /// `Poll::Pending` before every read
+    struct Trickle {
+        data: &'static [u8],
+        pos: usize,
+        ready: bool,
+    }
+
+    impl Trickle {
+        fn new(data: &'static [u8]) -> futures::io::BufReader<Self> {
+            futures::io::BufReader::with_capacity(
+                8,
+                Trickle {
+                    data,
+                    pos: 0,
+                    ready: false,
+                },
+            )
+        }
+    }
+
+    impl futures::io::AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+
+            let n = buf.len().min(3).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    #[test]
+    fn test_async_fastq_reader() {
+        use futures::executor::block_on;
+        use futures::StreamExt;
+
+        let reader: AsyncFastqReader<_> = AsyncFastqReader::new(Trickle::new(FQ1));
+        let records: Vec<Record<Vec<u8>>> = block_on(reader.map(Result::unwrap).collect());
+
+        let mut sync: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(FQ1));
+        let expected: Vec<Record<Vec<u8>>> = Iterator::map(&mut sync, Result::unwrap).collect();
+        assert_eq!(records, expected);
+
+        let mut reader: AsyncFastqReader<_> = AsyncFastqReader::new(Trickle::new(b"@r1\nAC\n+"));
+        let e = block_on(reader.next()).unwrap().unwrap_err();
+        assert!(matches!(e.parse_error(), Some(ParseError::Truncated(_))));
+        assert!(block_on(reader.next()).is_none());
+    }
+
+    #[test]
+    fn test_async_fastq_pending() {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut reader: AsyncFastqReader<_> = AsyncFastqReader::new(Trickle::new(FQ1));
+        let mut polls = 0;
+        let record = loop {
+            polls += 1;
+            if let Poll::Ready(record) = Pin::new(&mut reader).poll_next(&mut cx) {
+                break record;
+            }
+        };
+
+        // the first record is assembled over many polls
+        assert!(polls > 10);
+        assert_eq!(record.unwrap().unwrap().raw_fields, b"SEQ_ID_1");
+    }
+
+    #[test]
+    fn test_async_fastq_recovery() {
+        use futures::executor::block_on;
+        use futures::StreamExt;
+
+        let mut reader: AsyncFastqReader<_> =
+            AsyncFastqReader::new(Trickle::new(FQ_CORRUPT)).recovery(true);
+        let results: Vec<_> = block_on((&mut reader).collect());
+
+        let mut sync: FastqReader<Cursor<&[u8]>> =
+            FastqReader::new(Cursor::new(FQ_CORRUPT)).recovery(true);
+        let expected: Vec<_> = Iterator::collect(&mut sync);
+
+        assert_eq!(results.len(), expected.len());
+        for (result, expected) in results.iter().zip(&expected) {
+            match (result, expected) {
+                (Ok(r), Ok(e)) => assert_eq!(r, e),
+                (Err(r), Err(e)) => assert_eq!(r.position(), e.position()),
+                _ => panic!("async and blocking readers disagree"),
+            }
+        }
+        assert_eq!(reader.skipped_bytes(), sync.skipped_bytes());
+
+        let mut reader: AsyncFastqReader<_> =
+            AsyncFastqReader::new(Trickle::new(FQ_CORRUPT)).validation(Validation::Lenient);
+        assert_eq!(block_on((&mut reader).count()), 5);
+    }
+
+    #[test]
+    fn test_fastq_read_record() {
+        let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(FQ1));
+        let mut record: Record<Vec<u8>> = Record::default();
+
+        assert!(reader.read_record(&mut record).unwrap());
+        assert_eq!(record.fields(), b"SEQ_ID_1");
+        assert_eq!(record.raw_seq, b"ACTCGATCGCGACGAA");
+        let seq = record.raw_seq.as_ptr();
+
+        // the second record is shorter, so it fits in the same allocation
+        assert!(reader.read_record(&mut record).unwrap());
+        assert_eq!(record.fields(), b"SEQ_ID_2");
+        assert_eq!(record.raw_seq, b"CATCGACTACGGCG");
+        assert_eq!(record.raw_quality.as_deref(), Some(&b"GGGGGGGGGGGGGG"[..]));
+        assert_eq!(record.raw_seq.as_ptr(), seq);
+
+        assert!(!reader.read_record(&mut record).unwrap());
+        assert_eq!(record.fields(), b"SEQ_ID_2");
+    }
+
+    #[test]
+    fn test_fastq_read_record_errors() {
+        let mut reader: FastqReader<Cursor<&[u8]>> =
+            FastqReader::new(Cursor::new(FQ_CORRUPT)).recovery(true);
+        let mut record: Record<Vec<u8>> = Record::default();
+
+        let mut fields = Vec::new();
+        let mut errors = 0;
+        loop {
+            match reader.read_record(&mut record) {
+                Ok(true) => fields.push(record.fields().to_vec()),
+                Ok(false) => break,
+                Err(_) => {
+                    // the last good record is left in place
+                    assert_eq!(record.fields(), fields.last().unwrap());
+                    errors += 1;
+                }
+            }
+        }
+
+        assert_eq!(fields, [b"r1".to_vec(), b"r3".to_vec(), b"r4".to_vec()]);
+        assert_eq!(errors, 3);
+    }
+
+    fn generate_fastq(n: usize) -> Vec<u8> {
+        (0..n)
+            .flat_map(|i| {
+                format!("@read_{i} length=36\nACGTTGCAACGTTGCAACGTTGCAACGTTGCAACGT\n+\nIIIIIIIIIIIIIIIIII!!!!!!!!!!!!!!!!!!\n")
+                    .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sliding_reader() {
+        let data = generate_fastq(1000);
+        let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(&data));
+        let expected: Vec<Record<Vec<u8>>> = (&mut reader).map(Result::unwrap).collect();
+
+        // capacities smaller than, around and larger than a record
+        for capacity in [1, 16, 100, 1000, 1 << 20] {
+            let mut sliding: SlidingFastqReader<BufReader<&[u8]>> =
+                SlidingFastqReader::new(BufReader::with_capacity(capacity, &data[..]));
+            let mut n = 0;
+            while let Some(record) = sliding.next_record() {
+                assert_eq!(record.unwrap().to_owned(), expected[n]);
+                n += 1;
+            }
+            assert_eq!(n, 1000);
+        }
+
+        // records are lent from the reader's buffer without copying
+        let mut sliding: SlidingFastqReader<&[u8]> = SlidingFastqReader::new(&data[..]);
+        let input = data.as_ptr_range();
+        while let Some(record) = sliding.next_record() {
+            assert!(input.contains(&record.unwrap().raw_seq.as_ptr()));
+        }
+    }
+
+    #[test]
+    fn test_sliding_reader_errors() {
+        let mut sliding: SlidingFastqReader<BufReader<&[u8]>> =
+            SlidingFastqReader::new(BufReader::with_capacity(8, FQ_CORRUPT))
+                .validation(Validation::Strict);
+        let fastq: Fastq = Fastq::new(FQ_CORRUPT).validation(Validation::Strict);
+
+        for expected in fastq {
+            let record = sliding.next_record().unwrap();
+            match (record, expected) {
+                (Ok(r), Ok(e)) => assert_eq!(r.to_owned(), e.to_owned()),
+                (Err(r), Err(e)) => {
+                    assert_eq!(r.position(), e.position());
+                    assert_eq!(r.snippet(), e.snippet());
+                }
+                _ => panic!("sliding and borrowed parsers disagree"),
+            }
+        }
+        assert!(sliding.next_record().is_none());
+    }
+
+    // wrapped records, with quality lines starting with `@` and `+`
+    const FQ_WRAPPED: &[u8] = b"@SEQ_ID_1
+ACTCGATC
+GCGACGAA
++
+@FFFFFFF
++FFFFEBA
+@SEQ_ID_2
+CATCG
++SEQ_ID_2
+@GGGG
+@SEQ_ID_3
+
++
+
+";
+
+    #[test]
+    fn test_multiline_fastq() {
+        let records: Vec<Record<Cow<[u8]>, Vec<u8>>> = Fastq::<Vec<u8>>::new(FQ_WRAPPED)
+            .multiline()
+            .validation(Validation::Strict)
+            .map(|r| r.unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].fields(), b"SEQ_ID_1");
+        assert_eq!(&records[0].raw_seq[..], b"ACTCGATCGCGACGAA");
+        assert_eq!(
+            records[0].raw_quality.as_deref(),
+            Some(&b"@FFFFFFF+FFFFEBA"[..])
+        );
+        // unwrapped records are borrowed
+        assert!(matches!(records[1].raw_seq, Cow::Borrowed(b"CATCG")));
+        assert_eq!(records[1].raw_quality.as_deref(), Some(&b"@GGGG"[..]));
+        assert_eq!(&records[2].raw_seq[..], b"");
+
+        let mut reader: FastqReader<Cursor<&[u8]>> =
+            FastqReader::new(Cursor::new(FQ_WRAPPED)).multiline(true);
+        let owned: Vec<Record<Vec<u8>>> = (&mut reader).map(|r| r.unwrap()).collect();
+        let expected: Vec<Record<Vec<u8>>> = records.iter().map(Record::to_owned).collect();
+        assert_eq!(owned, expected);
+
+        // four line records parse the same way in multiline mode
+        let multiline: Vec<_> = MultilineFastq::<Vec<u8>>::new(FQ1)
+            .map(|r| r.unwrap().to_owned())
+            .collect();
+        let fastq: Vec<_> = Fastq::<Vec<u8>>::new(FQ1)
+            .map(|r| r.unwrap().to_owned())
+            .collect();
+        assert_eq!(multiline, fastq);
+    }
+
+    #[test]
+    fn test_multiline_errors() {
+        // the quality string is longer than the sequence
+        let fq = b"@r1\nACGT\nAC\n+\nIIII\nIIII\n@r2\nAC\n+\nII\n";
+
+        let results: Vec<_> = MultilineFastq::<Vec<u8>>::new(fq).collect();
+        assert_eq!(results.len(), 2);
+        let e = results[0].as_ref().unwrap_err();
+        let pos = Position {
+            record: 0,
+            line: 5,
+            offset: 14,
+        };
+        assert!(matches!(e.parse_error(), Some(ParseError::MismatchedLengths(p)) if *p == pos));
+        assert_eq!(e.snippet(), Some(&b"IIIIIIII"[..]));
+        assert!(results[1].is_ok());
+
+        let mut reader: FastqReader<Cursor<&[u8]>> =
+            FastqReader::new(Cursor::new(&fq[..])).multiline(true);
+        let e = (&mut reader).next().unwrap().unwrap_err();
+        assert_eq!(e.position(), Some(pos));
+        assert!((&mut reader).next().unwrap().is_ok());
+        assert!((&mut reader).next().is_none());
+
+        // missing separator or quality
+        for fq in [&b"@r1\nACGT\nACGT\n"[..], b"@r1\nACGT\n+\nII\n"] {
+            let e = MultilineFastq::<Vec<u8>>::new(fq)
+                .next()
+                .unwrap()
+                .unwrap_err();
+            assert!(matches!(e.parse_error(), Some(ParseError::Truncated(_))));
+
+            let mut reader: FastqReader<Cursor<&[u8]>> =
+                FastqReader::new(Cursor::new(fq)).multiline(true);
+            let r = (&mut reader).next().unwrap().unwrap_err();
+            assert_eq!(r.position(), e.position());
+        }
+    }
+
+    // fields, sequence and quality of the records in `line_ending_cases`
+    const LINE_ENDING_RECORDS: [(&[u8], &[u8], &[u8]); 3] = [
+        (b"r1", b"ACGT", b"@III"),
+        (b"r2", b"", b""),
+        (b"r3", b"GG", b"+I"),
+    ];
+
+    /// `LINE_ENDING_RECORDS` with every combination of line endings and
+    /// final newline
+    fn line_ending_cases() -> Vec<Vec<u8>> {
+        let mut cases = Vec::new();
+        for eol in [&b"\n"[..], b"\r\n"] {
+            for final_eol in [true, false] {
+                let mut data = Vec::new();
+                for (fields, seq, quality) in LINE_ENDING_RECORDS {
+                    for line in [&[b"@", fields].concat()[..], seq, b"+", quality] {
+                        data.extend_from_slice(line);
+                        data.extend_from_slice(eol);
+                    }
+                }
+                if !final_eol {
+                    data.truncate(data.len() - eol.len());
+                }
+                cases.push(data);
+            }
+        }
+        cases
+    }
+
+    #[test]
+    fn test_line_endings() {
+        use futures::executor::block_on;
+
+        let expected: Vec<Record<Vec<u8>>> = LINE_ENDING_RECORDS
+            .iter()
+            .map(|(f, s, q)| Record::new(f.to_vec(), s.to_vec(), Some(q.to_vec())))
+            .collect();
+
+        for data in line_ending_cases() {
+            let data = &data[..];
+            let context = String::from_utf8_lossy(data);
+
+            let borrowed: Vec<Record<Vec<u8>>> = Fastq::<Vec<u8>>::new(data)
+                .validation(Validation::Strict)
+                .map(|r| r.unwrap().to_owned())
+                .collect();
+            assert_eq!(borrowed, expected, "Fastq: {context:?}");
+
+            let mut reader: FastqReader<Cursor<&[u8]>> =
+                FastqReader::new(Cursor::new(data)).validation(Validation::Strict);
+            let owned: Vec<Record<Vec<u8>>> = Iterator::map(&mut reader, Result::unwrap).collect();
+            assert_eq!(owned, expected, "FastqReader: {context:?}");
+
+            let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(data));
+            let mut record = Record::default();
+            let mut refilled = Vec::new();
+            while reader.read_record(&mut record).unwrap() {
+                refilled.push(record.clone());
+            }
+            assert_eq!(refilled, expected, "read_record: {context:?}");
+
+            let reader: AsyncFastqReader<_> = AsyncFastqReader::new(
+                futures::io::BufReader::with_capacity(3, futures::io::Cursor::new(data)),
+            )
+            .validation(Validation::Strict);
+            let polled: Vec<Record<Vec<u8>>> = block_on(futures::StreamExt::collect(
+                futures::StreamExt::map(reader, Result::unwrap),
+            ));
+            assert_eq!(polled, expected, "AsyncFastqReader: {context:?}");
+
+            let mut sliding: SlidingFastqReader<BufReader<&[u8]>> =
+                SlidingFastqReader::new(BufReader::with_capacity(7, data))
+                    .validation(Validation::Strict);
+            let mut lent = Vec::new();
+            while let Some(record) = sliding.next_record() {
+                lent.push(record.unwrap().to_owned());
+            }
+            assert_eq!(lent, expected, "SlidingFastqReader: {context:?}");
+
+            let multiline: Vec<Record<Vec<u8>>> = MultilineFastq::<Vec<u8>>::new(data)
+                .validation(Validation::Strict)
+                .map(|r| r.unwrap().to_owned())
+                .collect();
+            assert_eq!(multiline, expected, "MultilineFastq: {context:?}");
+
+            let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(data))
+                .validation(Validation::Strict)
+                .multiline(true);
+            let owned: Vec<Record<Vec<u8>>> = Iterator::map(&mut reader, Result::unwrap).collect();
+            assert_eq!(owned, expected, "multiline FastqReader: {context:?}");
+        }
+    }
+
+    #[test]
+    fn test_line_endings_truncated() {
+        use futures::executor::block_on;
+
+        for data in [&b"@r1\r\nAC\r\n+"[..], b"@r1\r\nAC\r\n+\r\n", b"@r1\nAC"] {
+            let e = Fastq::<Vec<u8>>::new(data).next().unwrap().unwrap_err();
+            let pos = e.position();
+            assert!(matches!(e.parse_error(), Some(ParseError::Truncated(_))));
+
+            let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(data));
+            let e = (&mut reader).next().unwrap().unwrap_err();
+            assert_eq!(e.position(), pos);
+
+            let mut reader: AsyncFastqReader<_> =
+                AsyncFastqReader::new(futures::io::Cursor::new(data));
+            let e = block_on(futures::StreamExt::next(&mut reader))
+                .unwrap()
+                .unwrap_err();
+            assert_eq!(e.position(), pos);
+
+            let mut sliding: SlidingFastqReader<&[u8]> = SlidingFastqReader::new(data);
+            let e = sliding.next_record().unwrap().unwrap_err();
+            assert_eq!(e.position(), pos);
+        }
+    }
+
+    /// Compare the throughput of the FASTQ readers:
+    /// `cargo test --release -- --ignored --nocapture bench_fastq_readers`
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_fastq_readers() {
+        use std::time::Instant;
+
+        let data = generate_fastq(2_000_000);
+        let mb = f64::from(u32::try_from(data.len()).unwrap()) / 1e6;
+        let report = |name: &str, start: Instant, n: usize| {
+            assert_eq!(n, 2_000_000);
+            let secs = start.elapsed().as_secs_f64();
+            println!("{name:>20}: {:8.1} MB/s", mb / secs);
+        };
+
+        let start = Instant::now();
+        let mut reader: FastqReader<&[u8]> = FastqReader::new(&data[..]);
+        let n = (&mut reader).map(Result::unwrap).count();
+        report("FastqReader", start, n);
+
+        let start = Instant::now();
+        let mut reader: FastqReader<&[u8]> = FastqReader::new(&data[..]);
+        let mut record = Record::default();
+        let mut n = 0;
+        while reader.read_record(&mut record).unwrap() {
+            n += 1;
+        }
+        report("read_record", start, n);
+
+        let start = Instant::now();
+        let mut reader: SlidingFastqReader<&[u8]> = SlidingFastqReader::new(&data[..]);
+        let mut n = 0;
+        while let Some(record) = reader.next_record() {
+            record.unwrap();
+            n += 1;
+        }
+        report("SlidingFastqReader", start, n);
+
+        let start = Instant::now();
+        let n = Fastq::<Vec<u8>>::new(&data).map(Result::unwrap).count();
+        report("Fastq", start, n);
+    }
 }
@@ -11,12 +11,20 @@
 //! Shared `Record` type by `Fastq` and `Fasta` streams:
 //!
 //! ```
-//! use bio_streams::record::Phred;
+//! use bio_streams::fastq::Fastq;
+//! use bio_streams::record::Record;
 //!
-//! pub struct Record<T: for<'a> TryFrom<&'a [u8]> = Vec<u8>> {
-//!    pub fields: Vec<u8>,
-//!    pub seq: T,
-//!    pub quality: Option<Vec<Phred>>, // fasta records set quality to `None`//!
+//! let fq: &[u8] = b"@SEQ_ID_1\nACGT\n+\nIIII\n";
+//!
+//! for record in Fastq::<Vec<u8>>::new(fq) {
+//!     let record: Record<&[u8], Vec<u8>> = record.unwrap();
+//!     assert_eq!(record.fields(), b"SEQ_ID_1");
+//!     assert_eq!(record.seq().unwrap(), b"ACGT");
+//!     assert_eq!(record.quality().unwrap().len(), 4); // one quality score per base
+//!
+//!     // borrowed records can be copied into owned records
+//!     let owned: Record<Vec<u8>> = record.to_owned();
+//!     assert_eq!(owned.fields(), b"SEQ_ID_1");
 //! }
 //! ```
 //!
@@ -24,50 +32,40 @@
 //!
 //! ## Examples
 //!
-//! ### Stream a pair of fastqs and check some conditions on their name fields
+//! ### Stream a pair of fastqs and check that their mates match
 //! ```text
-//! // Open a pair of gzipped fastq files as streams of `Record`s with `Seq<Dna>` sequences
-//!
-//! let fq1: Fastq<BufReader<MultiGzDecoder<File>>> = Fastq::new(BufReader::new(
-//!    MultiGzDecoder::new(File::open(&file1).unwrap()),
-//! ));
-//!
-//! let fq2: Fastq<BufReader<MultiGzDecoder<File>>> = Fastq::new(BufReader::new(
-//!    MultiGzDecoder::new(File::open(&file2).unwrap()),
-//! ));
-//!
-//! for zipped in fq1.zip(fq2) {
-//!     match zipped {
-//!         (Ok(r1), Ok(r2)) => {
-//!            // check that the last characters of the name strings are 1 and 2
-//!            if r1.fields[r1.fields.len() - 1] != b'1' || r2.fields[r2.fields.len() - 1] != b'2'
-//!            {
-//!                eprintln!("paired records do not end in 1/2");
-//!            }
-//!
-//!            // check that the description fields are equal up to the last character
-//!            if r1.fields[..r1.fields.len() - 1] != r2.fields[..r2.fields.len() - 1] {
-//!                eprintln!("reads do not have the same names");
-//!            }
+//! // Open a pair of fastq files as streams of `Record`s with `Seq<Dna>` sequences.
+//! // gzip, bgzip, zstd, bzip2 and xz compressed files are detected and decompressed.
+//!
+//! let mut fq1: FastqReader<_, Seq<Dna>> = FastqReader::open(&file1).unwrap();
+//! let mut fq2: FastqReader<_, Seq<Dna>> = FastqReader::open(&file2).unwrap();
+//!
+//! // mates must have the same name, up to `/1` and `/2` suffixes or Illumina comments
+//! for pair in PairedFastq::new(&mut fq1, &mut fq2) {
+//!     match pair {
+//!         Ok((r1, r2)) => {
+//!             // ...
 //!         }
-//!         _ => {
-//!             eprintln!("Parse error in fastq files");
+//!         // mismatched names, files of different lengths or parse errors
+//!         Err(e) => {
+//!             eprintln!("{e}");
 //!         }
 //!     }
 //! }
 //! ```
 //!
+//! `AsyncPairedFastq` does the same for a pair of streams.
+//!
 //! ### Count amino acid k-mers
 //!
 //! ```text
-//! // this opens a gzipped data stream and parses it into `Records` with `Seq<Amino>` sequence fields
-//! let faa: Fasta<BufReader<File>, Seq<Amino>> =
-//!     Fasta::new(BufReader::new(File::open(&faa_file).unwrap()));
+//! // this opens a data stream and parses it into `Records` with `Seq<Amino>` sequence fields
+//! let mut faa: FastaReader<_, Seq<Amino>> = FastaReader::open(&faa_file).unwrap();
 //!
 //! // we can convert amino acid k-mers directly into usizes and use them to index into a table
 //! let mut histogram = Box::new([0u64; 1 << (K * Amino::BITS as usize)]);
 //!
-//! for contig in faa {
+//! for contig in &mut faa {
 //!    // here "contig" is a fasta record
 //!    for kmer in contig.unwrap().seq.kmers::<K>() {
 //!        histogram[usize::from(kmer)] += 1;
@@ -78,10 +76,22 @@
 
 #![warn(clippy::pedantic)]
 #![allow(clippy::must_use_candidate)]
-mod error;
-//pub mod fasta;
+#[cfg(feature = "gzip")]
+pub mod bgzf;
+pub mod compression;
+pub mod error;
+pub mod fai;
+pub mod fasta;
 pub mod fastq;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod paired;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod quality;
 pub mod record;
+pub mod scan;
+pub mod writer;
 //pub mod sam;
 //pub mod gfa;
 //pub mod paf;
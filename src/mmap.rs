@@ -0,0 +1,212 @@
+//! Memory mapped input for the borrowed `Fastq` and `Fasta` parsers
+
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::fasta::Fasta;
+use crate::fastq::Fastq;
+
+enum Source {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+/// The contents of a file, memory mapped if possible or otherwise read into
+/// memory
+///
+/// The file must not be modified while it is mapped.
+pub struct MappedFile {
+    source: Source,
+}
+
+impl MappedFile {
+    /// # Errors
+    /// Fails if the file can't be opened, or can neither be mapped nor read
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+
+        // Safety: the mapping is only read, and the file is required not to
+        // change while it is mapped
+        let source = if let Ok(mmap) = unsafe { Mmap::map(&file) } {
+            Source::Mapped(mmap)
+        } else {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            Source::Read(data)
+        };
+
+        Ok(MappedFile { source })
+    }
+
+    /// Whether the file was memory mapped rather than read into memory
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.source, Source::Mapped(_))
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.source {
+            Source::Mapped(mmap) => mmap,
+            Source::Read(data) => data,
+        }
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// A memory mapped FASTQ file. Its records borrow from the mapping.
+pub struct MappedFastq<S = Vec<u8>> {
+    file: MappedFile,
+    _s: PhantomData<S>,
+}
+
+impl<S> MappedFastq<S> {
+    /// # Errors
+    /// Fails if the file can't be opened, or can neither be mapped nor read
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(MappedFastq {
+            file: MappedFile::open(path)?,
+            _s: PhantomData,
+        })
+    }
+
+    pub fn file(&self) -> &MappedFile {
+        &self.file
+    }
+
+    pub fn iter<'a>(&'a self) -> Fastq<'a, S>
+    where
+        S: TryFrom<&'a [u8]>,
+    {
+        Fastq::new(&self.file)
+    }
+}
+
+impl<'a, S: TryFrom<&'a [u8]>> IntoIterator for &'a MappedFastq<S> {
+    type Item = <Fastq<'a, S> as Iterator>::Item;
+    type IntoIter = Fastq<'a, S>;
+
+    fn into_iter(self) -> Fastq<'a, S> {
+        self.iter()
+    }
+}
+
+/// A memory mapped FASTA file. Its records borrow from the mapping.
+pub struct MappedFasta<S = Vec<u8>> {
+    file: MappedFile,
+    _s: PhantomData<S>,
+}
+
+impl<S: for<'b> TryFrom<&'b [u8]>> MappedFasta<S> {
+    /// # Errors
+    /// Fails if the file can't be opened, or can neither be mapped nor read
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(MappedFasta {
+            file: MappedFile::open(path)?,
+            _s: PhantomData,
+        })
+    }
+
+    pub fn file(&self) -> &MappedFile {
+        &self.file
+    }
+
+    pub fn iter(&self) -> Fasta<'_, S> {
+        Fasta::new(&self.file)
+    }
+}
+
+impl<'a, S: for<'b> TryFrom<&'b [u8]>> IntoIterator for &'a MappedFasta<S> {
+    type Item = <Fasta<'a, S> as Iterator>::Item;
+    type IntoIter = Fasta<'a, S>;
+
+    fn into_iter(self) -> Fasta<'a, S> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::Record;
+    use std::path::PathBuf;
+
+    /// A file in the temporary directory which is removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_mapped_fastq() {
+        let fq = b"@SEQ_ID_1\nACTCGATCGCGACGAA\n+\nAFFFFFFFFFFFFEBA\n@SEQ_ID_2\nCATCG\n+\nGGGGG\n";
+        let file = TempFile::new("mapped.fq", fq);
+
+        let fastq: MappedFastq = Fastq::open_mmap(&file.0).unwrap();
+        assert!(fastq.file().is_mapped());
+        assert_eq!(&fastq.file()[..], fq);
+
+        let records: Vec<Record<&[u8], Vec<u8>>> = fastq.iter().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].fields(), b"SEQ_ID_2");
+        assert_eq!(records[1].raw_seq, b"CATCG");
+
+        // records can be iterated more than once
+        assert_eq!((&fastq).into_iter().count(), 2);
+    }
+
+    #[test]
+    fn test_mapped_fasta() {
+        let fa = b">SEQ_ID_1\nACGT\nACGT\n>SEQ_ID_2\nGGGG\n";
+        let file = TempFile::new("mapped.fa", fa);
+
+        let fasta: MappedFasta = Fasta::open_mmap(&file.0).unwrap();
+        let seqs: Vec<Vec<u8>> = fasta
+            .iter()
+            .map(|r| r.unwrap().raw_seq.into_owned())
+            .collect();
+        assert_eq!(seqs, [b"ACGTACGT".to_vec(), b"GGGG".to_vec()]);
+    }
+
+    #[test]
+    fn test_empty_file() {
+        let file = TempFile::new("empty.fq", b"");
+        let fastq: MappedFastq = MappedFastq::open(&file.0).unwrap();
+        assert!(fastq.file().is_empty());
+        assert_eq!(fastq.iter().count(), 0);
+
+        assert!(MappedFastq::<Vec<u8>>::open("/nonexistent/file.fq").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read_fallback() {
+        // files in procfs can be read but not mapped
+        let file = MappedFile::open("/proc/self/status").unwrap();
+        assert!(!file.is_mapped());
+        assert!(file.starts_with(b"Name:"));
+    }
+}
@@ -0,0 +1,571 @@
+//! Paired-end reads from a pair of FASTQ files or a single interleaved file
+//!
+//! Records are read from the R1 and R2 sources in lockstep, or in
+//! consecutive pairs from interleaved input. Mates must have
+//! the same read name, ignoring `/1` and `/2` suffixes and anything after
+//! the first whitespace. Mate numbers from those suffixes or an Illumina
+//! ` 1:N:0:...` comment must be 1 for the R1 record and 2 for the R2 record.
+
+use futures::Stream as AsyncIterator;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pub use crate::error::{Error, Format, ParseError};
+use crate::record::{Record, RecordData};
+
+/// The read name of a header, without comments or a mate suffix, and the
+/// mate number given by a `/1` or `/2` suffix or an Illumina ` 1:` or ` 2:`
+/// comment
+fn mate_name(fields: &[u8]) -> (&[u8], Option<u8>) {
+    let end = fields
+        .iter()
+        .position(u8::is_ascii_whitespace)
+        .unwrap_or(fields.len());
+    let name = &fields[..end];
+
+    if let [rest @ .., b'/', mate @ (b'1' | b'2')] = name {
+        return (rest, Some(mate - b'0'));
+    }
+    match fields[end..].trim_ascii_start() {
+        [mate @ (b'1' | b'2'), b':', ..] => (name, Some(mate - b'0')),
+        _ => (name, None),
+    }
+}
+
+/// Whether two headers name mates of the same read pair, with `r1` naming
+/// the first mate and `r2` the second. Headers without a mate number only
+/// need matching read names.
+pub fn is_mate_pair(r1: &[u8], r2: &[u8]) -> bool {
+    let (name1, mate1) = mate_name(r1);
+    let (name2, mate2) = mate_name(r2);
+    name1 == name2 && mate1.is_none_or(|m| m == 1) && mate2.is_none_or(|m| m == 2)
+}
+
+// one source ended before the other
+fn missing_mate<B: RecordData, S>(pair: usize, mate: u8, record: &Record<B, S>) -> Error {
+    Error::parse(
+        Format::Fastq,
+        ParseError::MissingMate(pair, mate),
+        Some(record.raw_fields.as_ref()),
+    )
+}
+
+/// Combine the next record from each source into a pair
+#[allow(clippy::type_complexity)]
+fn pair<B1: RecordData, S1, B2: RecordData, S2>(
+    pair: usize,
+    r1: Option<Result<Record<B1, S1>, Error>>,
+    r2: Option<Result<Record<B2, S2>, Error>>,
+) -> Option<Result<(Record<B1, S1>, Record<B2, S2>), Error>> {
+    Some(match (r1?, r2) {
+        (Ok(r1), None) => Err(missing_mate(pair, 2, &r1)),
+        (Err(e), _) | (_, Some(Err(e))) => Err(e),
+        (Ok(r1), Some(Ok(r2))) => {
+            if is_mate_pair(r1.raw_fields.as_ref(), r2.raw_fields.as_ref()) {
+                Ok((r1, r2))
+            } else {
+                Err(Error::parse(
+                    Format::Fastq,
+                    ParseError::MismatchedMates(pair),
+                    Some(r1.raw_fields.as_ref()),
+                ))
+            }
+        }
+    })
+}
+
+/// Reads mates from a pair of record sources in lockstep
+///
+/// ```
+/// use bio_streams::fastq::Fastq;
+/// use bio_streams::paired::PairedFastq;
+///
+/// let r1: &[u8] = b"@read_1/1\nACGT\n+\nIIII\n";
+/// let r2: &[u8] = b"@read_1/2\nTTGA\n+\nIIII\n";
+///
+/// let pairs = PairedFastq::new(Fastq::<Vec<u8>>::new(r1), Fastq::<Vec<u8>>::new(r2));
+/// for pair in pairs {
+///     let (r1, r2) = pair.unwrap();
+///     assert_eq!(r2.seq().unwrap(), b"TTGA");
+/// }
+/// ```
+///
+/// Errors from either source are passed through and the other mate is
+/// dropped. Mates with different names are reported as
+/// `ParseError::MismatchedMates` and reading continues with the next pair.
+/// If one source ends before the other a `ParseError::MissingMate` error is
+/// returned for the first unpaired record and the iterator ends.
+pub struct PairedFastq<R1, R2> {
+    r1: R1,
+    r2: R2,
+    pairs: usize,
+    done: bool,
+}
+
+impl<R1, R2> PairedFastq<R1, R2> {
+    pub fn new(r1: R1, r2: R2) -> Self {
+        PairedFastq {
+            r1,
+            r2,
+            pairs: 0,
+            done: false,
+        }
+    }
+
+    /// Number of pairs read so far, including mismatched pairs
+    pub fn pairs(&self) -> usize {
+        self.pairs
+    }
+
+    pub fn into_inner(self) -> (R1, R2) {
+        (self.r1, self.r2)
+    }
+}
+
+impl<B1, S1, B2, S2, R1, R2> Iterator for PairedFastq<R1, R2>
+where
+    B1: RecordData,
+    B2: RecordData,
+    R1: Iterator<Item = Result<Record<B1, S1>, Error>>,
+    R2: Iterator<Item = Result<Record<B2, S2>, Error>>,
+{
+    type Item = Result<(Record<B1, S1>, Record<B2, S2>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = match self.r1.next() {
+            // check for leftover records in R2
+            None => self
+                .r2
+                .next()
+                .map(|r2| r2.and_then(|r2| Err(missing_mate(self.pairs, 1, &r2)))),
+            r1 => pair(self.pairs, r1, self.r2.next()),
+        };
+
+        match &next {
+            None
+            | Some(Err(Error::Parse {
+                error: ParseError::MissingMate(..),
+                ..
+            })) => self.done = true,
+            Some(_) => self.pairs += 1,
+        }
+        next
+    }
+}
+
+/// Reads mates from a pair of streams, such as `AsyncFastqReader`s, in
+/// lockstep. Pairs are checked in the same way as `PairedFastq`.
+pub struct AsyncPairedFastq<R1: AsyncIterator, R2: AsyncIterator> {
+    r1: R1,
+    r2: R2,
+    // records which arrived before their mate, `Some(None)` once a stream ends
+    #[allow(clippy::option_option)]
+    queued1: Option<Option<R1::Item>>,
+    #[allow(clippy::option_option)]
+    queued2: Option<Option<R2::Item>>,
+    pairs: usize,
+    done: bool,
+}
+
+impl<R1: AsyncIterator + Unpin, R2: AsyncIterator + Unpin> AsyncPairedFastq<R1, R2> {
+    pub fn new(r1: R1, r2: R2) -> Self {
+        AsyncPairedFastq {
+            r1,
+            r2,
+            queued1: None,
+            queued2: None,
+            pairs: 0,
+            done: false,
+        }
+    }
+
+    /// Number of pairs read so far, including mismatched pairs
+    pub fn pairs(&self) -> usize {
+        self.pairs
+    }
+}
+
+impl<B1, S1, B2, S2, R1, R2> AsyncIterator for AsyncPairedFastq<R1, R2>
+where
+    B1: RecordData,
+    B2: RecordData,
+    R1: AsyncIterator<Item = Result<Record<B1, S1>, Error>> + Unpin,
+    R2: AsyncIterator<Item = Result<Record<B2, S2>, Error>> + Unpin,
+{
+    type Item = Result<(Record<B1, S1>, Record<B2, S2>), Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        if this.queued1.is_none() {
+            if let Poll::Ready(r1) = Pin::new(&mut this.r1).poll_next(cx) {
+                this.queued1 = Some(r1);
+            }
+        }
+        if this.queued2.is_none() {
+            if let Poll::Ready(r2) = Pin::new(&mut this.r2).poll_next(cx) {
+                this.queued2 = Some(r2);
+            }
+        }
+
+        let (r1, r2) = match (this.queued1.take(), this.queued2.take()) {
+            (Some(r1), Some(r2)) => (r1, r2),
+            (queued1, queued2) => {
+                this.queued1 = queued1;
+                this.queued2 = queued2;
+                return Poll::Pending;
+            }
+        };
+
+        let next = match r1 {
+            None => r2.map(|r2| r2.and_then(|r2| Err(missing_mate(this.pairs, 1, &r2)))),
+            r1 => pair(this.pairs, r1, r2),
+        };
+
+        match &next {
+            None
+            | Some(Err(Error::Parse {
+                error: ParseError::MissingMate(..),
+                ..
+            })) => this.done = true,
+            Some(_) => this.pairs += 1,
+        }
+        Poll::Ready(next)
+    }
+}
+
+/// Reads mates from interleaved input, where each R1 record is followed by
+/// its R2 mate. Pairs are checked in the same way as `PairedFastq`.
+///
+/// ```
+/// use bio_streams::fastq::Fastq;
+/// use bio_streams::paired::InterleavedFastq;
+///
+/// let fq: &[u8] = b"@read_1/1\nACGT\n+\nIIII\n@read_1/2\nTTGA\n+\nIIII\n";
+///
+/// let pairs: Vec<_> = InterleavedFastq::new(Fastq::<Vec<u8>>::new(fq)).collect();
+/// assert_eq!(pairs.len(), 1);
+/// ```
+///
+/// An odd record at the end of the input is reported as
+/// `ParseError::MissingMate` and the iterator ends. A parse error in an R1
+/// record also consumes the record following it, as its mate.
+pub struct InterleavedFastq<R> {
+    records: R,
+    pairs: usize,
+    done: bool,
+}
+
+impl<R> InterleavedFastq<R> {
+    pub fn new(records: R) -> Self {
+        InterleavedFastq {
+            records,
+            pairs: 0,
+            done: false,
+        }
+    }
+
+    /// Number of pairs read so far, including mismatched pairs
+    pub fn pairs(&self) -> usize {
+        self.pairs
+    }
+
+    pub fn into_inner(self) -> R {
+        self.records
+    }
+}
+
+impl<B, S, R> Iterator for InterleavedFastq<R>
+where
+    B: RecordData,
+    R: Iterator<Item = Result<Record<B, S>, Error>>,
+{
+    type Item = Result<(Record<B, S>, Record<B, S>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = match self.records.next() {
+            None => None,
+            r1 => pair(self.pairs, r1, self.records.next()),
+        };
+
+        match &next {
+            None
+            | Some(Err(Error::Parse {
+                error: ParseError::MissingMate(..),
+                ..
+            })) => self.done = true,
+            Some(_) => self.pairs += 1,
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fastq::{AsyncFastqReader, Fastq, FastqReader};
+    use futures::executor::block_on;
+    use futures::StreamExt;
+    use std::io::Cursor;
+
+    const R1: &[u8] =
+        b"@pair_1/1\nACGT\n+\nIIII\n@pair_2/1\nGGCC\n+\nIIII\n@pair_3/1\nTTAA\n+\nIIII\n";
+    const R2: &[u8] =
+        b"@pair_1/2\nCCAA\n+\nIIII\n@pair_2/2\nTTGG\n+\nIIII\n@pair_3/2\nAACC\n+\nIIII\n";
+
+    fn pairs<'a>(r1: &'a [u8], r2: &'a [u8]) -> PairedFastq<Fastq<'a>, Fastq<'a>> {
+        PairedFastq::new(Fastq::new(r1), Fastq::new(r2))
+    }
+
+    #[test]
+    fn test_read_names() {
+        assert!(is_mate_pair(b"read_1/1", b"read_1/2"));
+        assert!(is_mate_pair(b"read_1", b"read_1"));
+        assert!(is_mate_pair(
+            b"M00123:8:000000000-A1B2C:1:1101:15589:1331 1:N:0:ATCACG",
+            b"M00123:8:000000000-A1B2C:1:1101:15589:1331 2:N:0:ATCACG"
+        ));
+        assert!(is_mate_pair(b"read_1/1 length=4", b"read_1/2\tlength=4"));
+
+        assert!(!is_mate_pair(b"read_1/1", b"read_2/2"));
+        assert!(!is_mate_pair(b"read_1/1", b"read_1/3"));
+        assert!(!is_mate_pair(b"read_1 1:N:0:A", b"read_11 2:N:0:A"));
+
+        // mates paired with themselves or swapped
+        assert!(!is_mate_pair(b"read_1/1", b"read_1/1"));
+        assert!(!is_mate_pair(b"read_1/2", b"read_1/2"));
+        assert!(!is_mate_pair(b"read_1/2", b"read_1/1"));
+        assert!(!is_mate_pair(b"read_1 1:N:0:A", b"read_1 1:N:0:A"));
+        assert!(!is_mate_pair(b"read_1 2:N:0:A", b"read_1 1:N:0:A"));
+        assert!(!is_mate_pair(b"read_1/1 x", b"read_1 1:N:0:A"));
+
+        // a mate number on one side only
+        assert!(is_mate_pair(b"read_1/1", b"read_1"));
+        assert!(!is_mate_pair(b"read_1", b"read_1/1"));
+    }
+
+    #[test]
+    fn test_paired_fastq() {
+        let mut paired = pairs(R1, R2);
+        let mates: Vec<(Vec<u8>, Vec<u8>)> = (&mut paired)
+            .map(|pair| {
+                let (r1, r2) = pair.unwrap();
+                (r1.raw_seq.to_vec(), r2.raw_seq.to_vec())
+            })
+            .collect();
+
+        assert_eq!(mates.len(), 3);
+        assert_eq!(mates[1], (b"GGCC".to_vec(), b"TTGG".to_vec()));
+        assert_eq!(paired.pairs(), 3);
+
+        // owned records from `FastqReader`s
+        let mut fq1: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(R1));
+        let mut fq2: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(R2));
+        assert_eq!(PairedFastq::new(&mut fq1, &mut fq2).count(), 3);
+    }
+
+    #[test]
+    fn test_mismatched_mates() {
+        let r2 = b"@pair_1/2\nCCAA\n+\nIIII\n@other/2\nTTGG\n+\nIIII\n@pair_3/2\nAACC\n+\nIIII\n";
+        let results: Vec<_> = pairs(R1, r2).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        let e = results[1].as_ref().unwrap_err();
+        assert!(matches!(
+            e.parse_error(),
+            Some(ParseError::MismatchedMates(1))
+        ));
+        assert_eq!(e.snippet(), Some(&b"pair_2/1"[..]));
+        assert!(results[2].is_ok());
+
+        // R1 paired with itself, or with the files swapped
+        assert!(pairs(R1, R1).all(|pair| pair.is_err()));
+        assert!(pairs(R2, R1).all(|pair| pair.is_err()));
+    }
+
+    #[test]
+    fn test_missing_mates() {
+        let short = &R2[..44];
+
+        let results: Vec<_> = pairs(R1, short).collect();
+        assert_eq!(results.len(), 3);
+        let e = results[2].as_ref().unwrap_err();
+        assert!(matches!(
+            e.parse_error(),
+            Some(ParseError::MissingMate(2, 2))
+        ));
+        assert_eq!(e.snippet(), Some(&b"pair_3/1"[..]));
+
+        // R1 is shorter, and the iterator ends at the first unpaired record
+        let results: Vec<_> = pairs(&R1[..22], R2).collect();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(
+            results[1].as_ref().unwrap_err().parse_error(),
+            Some(ParseError::MissingMate(1, 1))
+        ));
+
+        assert_eq!(pairs(b"", b"").count(), 0);
+    }
+
+    #[test]
+    fn test_interleaved_fastq() {
+        // each record is 22 bytes long
+        let interleaved = [
+            &R1[..22],
+            &R2[..22],
+            &R1[22..44],
+            &R2[22..44],
+            &R1[44..],
+            &R2[44..],
+        ]
+        .concat();
+
+        let mut pairs = InterleavedFastq::new(Fastq::<Vec<u8>>::new(&interleaved));
+        let names: Vec<(&[u8], &[u8])> = (&mut pairs)
+            .map(|pair| {
+                let (r1, r2) = pair.unwrap();
+                (r1.fields(), r2.fields())
+            })
+            .collect();
+        assert_eq!(names[2], (&b"pair_3/1"[..], &b"pair_3/2"[..]));
+        assert_eq!(pairs.pairs(), 3);
+
+        // an odd record at the end
+        let results: Vec<_> =
+            InterleavedFastq::new(Fastq::<Vec<u8>>::new(&interleaved[..110])).collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[2].as_ref().unwrap_err().parse_error(),
+            Some(ParseError::MissingMate(2, 2))
+        ));
+
+        // the input isn't read again once it ends
+        let polls = std::cell::Cell::new(0);
+        let mut records = Fastq::<Vec<u8>>::new(&interleaved[..110]);
+        let counted = std::iter::from_fn(|| {
+            polls.set(polls.get() + 1);
+            Iterator::next(&mut records)
+        });
+        let mut pairs = InterleavedFastq::new(counted);
+        assert_eq!(pairs.by_ref().count(), 3);
+        assert!(pairs.next().is_none());
+        assert_eq!(pairs.pairs(), 2);
+        assert_eq!(polls.get(), 6);
+
+        let polls = std::cell::Cell::new(0);
+        let mut records = Fastq::<Vec<u8>>::new(&interleaved[..44]);
+        let counted = std::iter::from_fn(|| {
+            polls.set(polls.get() + 1);
+            Iterator::next(&mut records)
+        });
+        let mut pairs = InterleavedFastq::new(counted);
+        assert_eq!(pairs.by_ref().count(), 1);
+        assert!(pairs.next().is_none());
+        assert_eq!(polls.get(), 3);
+
+        // a bad R1 record is reported with its mate consumed
+        let bad = [&b"@pair_1/1\nACGT\n-\nIIII\n"[..], &interleaved[22..]].concat();
+        let mut pairs = InterleavedFastq::new(Fastq::<Vec<u8>>::new(&bad));
+        assert!(pairs.next().unwrap().is_err());
+        let (r1, _) = pairs.next().unwrap().unwrap();
+        assert_eq!(r1.fields(), b"pair_2/1");
+
+        // R2 records before their R1 mates
+        let swapped = [&R2[..22], &R1[..22]].concat();
+        let results: Vec<_> = InterleavedFastq::new(Fastq::<Vec<u8>>::new(&swapped)).collect();
+        assert!(matches!(
+            results[0].as_ref().unwrap_err().parse_error(),
+            Some(ParseError::MismatchedMates(0))
+        ));
+
+        // R1 records without interleaved mates
+        let results: Vec<_> = InterleavedFastq::new(Fastq::<Vec<u8>>::new(R1)).collect();
+        assert!(matches!(
+            results[0].as_ref().unwrap_err().parse_error(),
+            Some(ParseError::MismatchedMates(0))
+        ));
+    }
+
+    #[test]
+    fn test_paired_errors() {
+        // a truncated record in R2 is passed through
+        let results: Vec<_> = pairs(&R1[..22], &R2[..16]).collect();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            results[0].as_ref().unwrap_err().parse_error(),
+            Some(ParseError::Truncated(_))
+        ));
+    }
+
+    /// Stream which returns `Poll::Pending` before every item
+    struct Slow<S> {
+        inner: S,
+        ready: bool,
+    }
+
+    impl<S: AsyncIterator + Unpin> AsyncIterator for Slow<S> {
+        type Item = S::Item;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            Pin::new(&mut self.inner).poll_next(cx)
+        }
+    }
+
+    #[test]
+    fn test_async_paired_fastq() {
+        let fq1: AsyncFastqReader<_> = AsyncFastqReader::new(futures::io::Cursor::new(R1));
+        let fq2: AsyncFastqReader<_> = AsyncFastqReader::new(futures::io::Cursor::new(R2));
+
+        let names: Vec<Vec<u8>> = block_on(
+            AsyncPairedFastq::new(fq1, fq2)
+                .map(|pair| pair.unwrap().1.raw_fields)
+                .collect(),
+        );
+        assert_eq!(
+            names,
+            [
+                b"pair_1/2".to_vec(),
+                b"pair_2/2".to_vec(),
+                b"pair_3/2".to_vec()
+            ]
+        );
+
+        // mates which arrive before each other are kept
+        let slow = Slow {
+            inner: Fastq::<Vec<u8>>::new(R2),
+            ready: false,
+        };
+        let paired = AsyncPairedFastq::new(Fastq::<Vec<u8>>::new(R1), slow);
+        let results: Vec<_> = block_on(paired.collect());
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(Result::is_ok));
+
+        // the synchronous parsers are streams too
+        let paired =
+            AsyncPairedFastq::new(Fastq::<Vec<u8>>::new(R1), Fastq::<Vec<u8>>::new(&R2[..44]));
+        let results: Vec<_> = block_on(paired.collect());
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[2].as_ref().unwrap_err().parse_error(),
+            Some(ParseError::MissingMate(2, 2))
+        ));
+    }
+}
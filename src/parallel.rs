@@ -0,0 +1,259 @@
+//! Parallel parsing of in-memory FASTQ
+//!
+//! The input is split into chunks at record boundaries, and each chunk is
+//! parsed with a `Fastq` parser on the rayon thread pool.
+
+use rayon::prelude::*;
+
+pub use crate::error::Error;
+use crate::fastq::{is_record_start, Fastq, Validation};
+pub use crate::record::Record;
+use crate::scan;
+
+// default number of bytes per chunk
+const CHUNK_SIZE: usize = 4 << 20;
+
+/// Offset of the line following `pos`, or the end of the buffer
+fn next_line(buf: &[u8], pos: usize) -> usize {
+    scan::find_newline(&buf[pos..]).map_or(buf.len(), |n| pos + n + 1)
+}
+
+/// Whether a record starts at `pos`. Quality strings may also start with
+/// `@`, so the header must be followed by a `+` separator two lines later,
+/// matching sequence and quality lengths and then another `@` header or the
+/// end of the input.
+fn is_boundary(buf: &[u8], pos: usize) -> bool {
+    let mut lines: [&[u8]; 4] = [&[]; 4];
+    let mut start = pos;
+
+    for line in &mut lines {
+        let Some(n) = scan::find_newline(&buf[start..]) else {
+            return false;
+        };
+        *line = &buf[start..start + n];
+        start += n + 1;
+    }
+
+    is_record_start(&lines) && buf.get(start).is_none_or(|&b| b == b'@')
+}
+
+/// The first record boundary at or after `from`
+fn record_start(buf: &[u8], from: usize) -> usize {
+    let mut pos = from;
+    if pos > 0 && buf[pos - 1] != b'\n' {
+        pos = next_line(buf, pos);
+    }
+
+    while pos < buf.len() && !is_boundary(buf, pos) {
+        pos = next_line(buf, pos);
+    }
+    pos
+}
+
+/// Parallel parser for a FASTQ file held in memory, or memory mapped
+///
+/// Records are parsed in parallel and can be collected in their original
+/// order or consumed in unordered batches. For well formed input the results
+/// are identical to parsing with `Fastq`, positions of errors included.
+pub struct ParallelFastq<'a, S = Vec<u8>> {
+    buffer: &'a [u8],
+    chunk_size: usize,
+    validation: Validation,
+    _s: std::marker::PhantomData<S>,
+}
+
+impl<'a, S: TryFrom<&'a [u8]> + Send> ParallelFastq<'a, S> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        ParallelFastq {
+            buffer,
+            chunk_size: CHUNK_SIZE,
+            validation: Validation::default(),
+            _s: std::marker::PhantomData,
+        }
+    }
+
+    /// Approximate number of bytes parsed by each task
+    #[must_use]
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn validation(mut self, level: Validation) -> Self {
+        self.validation = level;
+        self
+    }
+
+    /// Offsets of the record boundaries which split the input into chunks,
+    /// starting with 0 and ending with the length of the input
+    pub fn boundaries(&self) -> Vec<usize> {
+        let buf = self.buffer;
+        let mut boundaries = vec![0];
+
+        let mut target = self.chunk_size;
+        while target < buf.len() {
+            let start = record_start(buf, target);
+            if start >= buf.len() {
+                break;
+            }
+            boundaries.push(start);
+            target = start + self.chunk_size;
+        }
+
+        boundaries.push(buf.len());
+        boundaries
+    }
+
+    /// A parser for each chunk, positioned so that record numbers, lines and
+    /// offsets are relative to the whole input
+    pub fn chunks(&self) -> Vec<Fastq<'a, S>> {
+        let boundaries = self.boundaries();
+
+        // count the lines before each chunk in parallel
+        let lines: Vec<usize> = boundaries
+            .par_windows(2)
+            .map(|w| scan::count_newlines(&self.buffer[w[0]..w[1]]))
+            .collect();
+
+        let mut line = 0;
+        boundaries
+            .windows(2)
+            .zip(lines)
+            .map(|(w, n)| {
+                let fastq = Fastq::new(&self.buffer[..w[1]])
+                    .validation(self.validation)
+                    .starting_at(w[0], line / 4, line + 1);
+                line += n;
+                fastq
+            })
+            .collect()
+    }
+
+    /// Parse each chunk in parallel, as a batch of records. Use `for_each` to
+    /// handle batches as they are parsed or `collect` to keep their order.
+    pub fn par_batches(
+        &self,
+    ) -> impl IndexedParallelIterator<Item = Vec<Result<Record<&'a [u8], S>, Error>>> {
+        self.chunks()
+            .into_par_iter()
+            .map(Iterator::collect::<Vec<_>>)
+    }
+
+    /// Parse the input in parallel. Collecting the records keeps their order.
+    pub fn par_records(&self) -> impl ParallelIterator<Item = Result<Record<&'a [u8], S>, Error>> {
+        self.chunks().into_par_iter().flat_map_iter(|fastq| fastq)
+    }
+
+    /// Parse the input in parallel, returning the records in order
+    pub fn records(&self) -> Vec<Result<Record<&'a [u8], S>, Error>> {
+        self.par_records().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // qualities starting with `@` and `+` make for ambiguous line starts
+    fn sample(n: usize) -> Vec<u8> {
+        (0..n)
+            .flat_map(|i| {
+                let len = 5 + i % 13;
+                let qual = if i % 2 == 0 { "@" } else { "+" }.repeat(len);
+                format!("@read_{i}\n{}\n+\n{qual}\n", &"ACGT".repeat(len)[..len]).into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let data = sample(1000);
+        let fastq: ParallelFastq = ParallelFastq::new(&data).chunk_size(100);
+        let boundaries = fastq.boundaries();
+
+        assert!(boundaries.len() > 100);
+        assert_eq!(boundaries.last(), Some(&data.len()));
+        for &b in &boundaries[..boundaries.len() - 1] {
+            assert!(data[b..].starts_with(b"@read_"));
+        }
+
+        // larger chunks than the input
+        let fastq: ParallelFastq = ParallelFastq::new(&data);
+        assert_eq!(fastq.boundaries(), [0, data.len()]);
+
+        let fastq: ParallelFastq = ParallelFastq::new(b"");
+        assert_eq!(fastq.boundaries(), [0, 0]);
+    }
+
+    #[test]
+    fn test_parallel_records() {
+        let data = sample(5000);
+        let expected: Vec<_> = Fastq::<Vec<u8>>::new(&data).collect();
+
+        for chunk_size in [1, 64, 1000, 1 << 20] {
+            let fastq: ParallelFastq = ParallelFastq::new(&data).chunk_size(chunk_size);
+            let records = fastq.records();
+
+            assert_eq!(records.len(), expected.len());
+            for (record, expected) in records.iter().zip(&expected) {
+                assert_eq!(record.as_ref().unwrap(), expected.as_ref().unwrap());
+            }
+
+            let n: usize = fastq.par_batches().map(|batch| batch.len()).sum();
+            assert_eq!(n, 5000);
+        }
+    }
+
+    #[test]
+    fn test_parallel_line_endings() {
+        let mut data: Vec<u8> = sample(500)
+            .split_inclusive(|&b| b == b'\n')
+            .flat_map(|line| [&line[..line.len() - 1], b"\r\n"].concat())
+            .collect();
+        data.truncate(data.len() - 2);
+
+        let expected: Vec<_> = Fastq::<Vec<u8>>::new(&data)
+            .map(|r| r.unwrap().to_owned())
+            .collect();
+        let records: Vec<_> = ParallelFastq::<Vec<u8>>::new(&data)
+            .chunk_size(100)
+            .records()
+            .into_iter()
+            .map(|r| r.unwrap().to_owned())
+            .collect();
+        assert_eq!(records.len(), 500);
+        assert_eq!(records, expected);
+    }
+
+    #[test]
+    fn test_parallel_errors() {
+        let mut data = sample(100);
+        // a bad separator in record 60
+        let at = Fastq::<Vec<u8>>::new(&data)
+            .take(60)
+            .map(|r| r.unwrap())
+            .map(|r| r.fields().len() + r.raw_seq.len() * 2 + 5)
+            .sum::<usize>();
+        let sep = at + data[at..].iter().position(|&b| b == b'+').unwrap();
+        data[sep] = b'-';
+
+        let expected: Vec<_> = Fastq::<Vec<u8>>::new(&data)
+            .validation(Validation::Strict)
+            .collect();
+        let records = ParallelFastq::<Vec<u8>>::new(&data)
+            .chunk_size(50)
+            .validation(Validation::Strict)
+            .records();
+
+        assert_eq!(records.len(), expected.len());
+        for (record, expected) in records.iter().zip(&expected) {
+            match (record, expected) {
+                (Ok(r), Ok(e)) => assert_eq!(r, e),
+                (Err(r), Err(e)) => assert_eq!(r.position(), e.position()),
+                _ => panic!("parallel and sequential parsers disagree"),
+            }
+        }
+        assert_eq!(records.iter().filter(|r| r.is_err()).count(), 1);
+    }
+}
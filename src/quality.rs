@@ -0,0 +1,686 @@
+//! FASTQ quality encodings
+//!
+//! Quality strings are Phred+33 (Sanger) in most modern files, but older
+//! Illumina pipelines wrote Phred+64 and the earliest Solexa scores, which
+//! are log odds rather than log probabilities. Qualities can be detected
+//! from their range and converted between encodings, and binned to fewer
+//! levels to compress better.
+
+use futures::{ready, Stream as AsyncIterator};
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pub use crate::error::Error;
+pub use crate::record::{Phred, Record, RecordData};
+
+/// How quality scores are encoded as characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// Phred+33, used by Sanger and Illumina 1.8+
+    #[default]
+    Sanger,
+    /// Phred+64, used by Illumina 1.3 to 1.7
+    Illumina,
+    /// Solexa+64 log odds scores, used by Solexa and Illumina 1.0
+    Solexa,
+}
+
+// Solexa scores start at -5
+const SOLEXA_MIN: i32 = -5;
+
+impl Encoding {
+    /// The character encoding a score of 0
+    pub fn offset(self) -> u8 {
+        match self {
+            Encoding::Sanger => 33,
+            Encoding::Illumina | Encoding::Solexa => 64,
+        }
+    }
+
+    /// Whether `b` is a valid character in this encoding
+    pub fn is_valid(self, b: u8) -> bool {
+        let min = match self {
+            Encoding::Solexa => b';',
+            _ => self.offset(),
+        };
+        (min..=b'~').contains(&b)
+    }
+
+    /// The Phred quality score of a character, saturating at the ends of
+    /// the encoding's range
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn decode(self, b: u8) -> u8 {
+        let b = b.min(b'~');
+        match self {
+            Encoding::Sanger | Encoding::Illumina => b.saturating_sub(self.offset()),
+            Encoding::Solexa => {
+                let q = f64::from((i32::from(b) - 64).max(SOLEXA_MIN));
+                (10.0 * (10_f64.powf(q / 10.0) + 1.0).log10()).round() as u8
+            }
+        }
+    }
+
+    /// The character encoding a Phred quality score, saturating at the
+    /// highest score the encoding can represent
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn encode(self, q: u8) -> u8 {
+        let max = b'~' - self.offset();
+        match self {
+            Encoding::Sanger | Encoding::Illumina => q.min(max) + self.offset(),
+            Encoding::Solexa => {
+                // a Phred score of 0 is a Solexa score of minus infinity
+                let s = (10.0 * (10_f64.powf(f64::from(q) / 10.0) - 1.0).log10()).round();
+                let s = if s.is_finite() { s as i32 } else { SOLEXA_MIN };
+                (s.clamp(SOLEXA_MIN, i32::from(max)) + 64) as u8
+            }
+        }
+    }
+
+    /// Guess the encoding of a set of quality strings from the range of
+    /// their characters. Returns `None` if there are no qualities or they
+    /// are outside of every encoding's range.
+    pub fn detect<I, Q>(qualities: I) -> Option<Encoding>
+    where
+        I: IntoIterator<Item = Q>,
+        Q: AsRef<[u8]>,
+    {
+        let mut detector = Detector::default();
+        for quality in qualities {
+            detector.add(quality.as_ref());
+        }
+        detector.guess()
+    }
+}
+
+/// Accumulates the range of quality characters to guess their encoding
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Detector {
+    // lowest and highest characters seen
+    range: Option<(u8, u8)>,
+}
+
+impl Detector {
+    pub fn add(&mut self, quality: &[u8]) {
+        for &b in quality {
+            self.range = Some(match self.range {
+                None => (b, b),
+                Some((min, max)) => (min.min(b), max.max(b)),
+            });
+        }
+    }
+
+    /// The most likely encoding of the qualities seen so far
+    pub fn guess(&self) -> Option<Encoding> {
+        let (min, max) = self.range?;
+        Some(match (min, max) {
+            (..b'!', _) | (_, b'\x7f'..) => return None,
+            // below the range of the +64 encodings, or high quality Phred+33
+            // rather than uniformly poor Phred+64
+            (..b';', _) | (_, ..b'K') => Encoding::Sanger,
+            // negative Solexa scores
+            (..b'@', _) => Encoding::Solexa,
+            _ => Encoding::Illumina,
+        })
+    }
+}
+
+/// Converts quality strings from one encoding to another with a lookup
+/// table. Scores are saturated to the target encoding's range.
+#[derive(Clone)]
+pub struct Converter {
+    from: Encoding,
+    to: Encoding,
+    table: [u8; 256],
+}
+
+impl Converter {
+    pub fn new(from: Encoding, to: Encoding) -> Self {
+        let mut table = [0; 256];
+        for (b, out) in (0..=u8::MAX).zip(table.iter_mut()) {
+            *out = to.encode(from.decode(b));
+        }
+        Converter { from, to, table }
+    }
+
+    pub fn from(&self) -> Encoding {
+        self.from
+    }
+
+    pub fn to(&self) -> Encoding {
+        self.to
+    }
+
+    /// Re-encode a quality string in place
+    pub fn convert(&self, quality: &mut [u8]) {
+        if self.from != self.to {
+            for b in quality {
+                *b = self.table[usize::from(*b)];
+            }
+        }
+    }
+
+    /// An owned copy of a record with its qualities re-encoded
+    pub fn convert_record<B: RecordData, S>(&self, record: &Record<B, S>) -> Record<Vec<u8>, S> {
+        let mut record = record.to_owned();
+        if let Some(quality) = &mut record.raw_quality {
+            self.convert(quality);
+        }
+        record
+    }
+}
+
+/// Iterator and stream adapter which re-encodes the qualities of records
+///
+/// ```
+/// use bio_streams::fastq::Fastq;
+/// use bio_streams::quality::{Encoding, Reencode};
+///
+/// // Phred+64 qualities
+/// let fq: &[u8] = b"@SEQ_ID_1\nACGT\n+\nhhhT\n";
+///
+/// let mut records = Reencode::detect(Fastq::<Vec<u8>>::new(fq), 1000, Encoding::Sanger);
+/// assert_eq!(records.from(), Encoding::Illumina);
+///
+/// let record = records.next().unwrap().unwrap();
+/// let scores: Vec<u8> = record.quality().unwrap().iter().map(|q| q.score()).collect();
+/// assert_eq!(scores, [40, 40, 40, 20]);
+/// ```
+///
+/// Streams such as `AsyncFastqReader` are detected with `detect_stream`.
+pub struct Reencode<I, S = Vec<u8>> {
+    records: I,
+    converter: Converter,
+    // records read ahead to detect the encoding, not yet re-encoded
+    buffered: VecDeque<Result<Record<Vec<u8>, S>, Error>>,
+}
+
+impl<I, S> Reencode<I, S> {
+    pub fn new(records: I, from: Encoding, to: Encoding) -> Self {
+        Reencode {
+            records,
+            converter: Converter::new(from, to),
+            buffered: VecDeque::new(),
+        }
+    }
+
+    // Sanger is assumed if the buffered records don't settle the encoding
+    fn detected(
+        records: I,
+        buffered: VecDeque<Result<Record<Vec<u8>, S>, Error>>,
+        to: Encoding,
+    ) -> Self {
+        let mut detector = Detector::default();
+        for record in buffered.iter().flatten() {
+            if let Some(quality) = &record.raw_quality {
+                detector.add(quality);
+            }
+        }
+        let from = detector.guess().unwrap_or_default();
+
+        Reencode {
+            records,
+            converter: Converter::new(from, to),
+            buffered,
+        }
+    }
+
+    /// The encoding of the input
+    pub fn from(&self) -> Encoding {
+        self.converter.from()
+    }
+
+    pub fn to(&self) -> Encoding {
+        self.converter.to()
+    }
+
+    fn next_buffered(&mut self) -> Option<Result<Record<Vec<u8>, S>, Error>> {
+        let mut record = self.buffered.pop_front()?;
+        if let Ok(Record {
+            raw_quality: Some(quality),
+            ..
+        }) = &mut record
+        {
+            self.converter.convert(quality);
+        }
+        Some(record)
+    }
+}
+
+impl<B, S, I> Reencode<I, S>
+where
+    B: RecordData,
+    I: Iterator<Item = Result<Record<B, S>, Error>>,
+{
+    /// Detect the encoding from up to the first `n` records, which are kept
+    /// and re-encoded with the rest. Sanger is assumed if it can't be
+    /// detected.
+    pub fn detect(mut records: I, n: usize, to: Encoding) -> Self {
+        let buffered = records
+            .by_ref()
+            .take(n)
+            .map(|record| record.map(|record| record.to_owned()))
+            .collect();
+        Self::detected(records, buffered, to)
+    }
+}
+
+impl<B, S, St> Reencode<St, S>
+where
+    B: RecordData,
+    St: AsyncIterator<Item = Result<Record<B, S>, Error>> + Unpin,
+{
+    /// Detect the encoding from up to the first `n` records of a stream,
+    /// which are kept and re-encoded with the rest. Sanger is assumed if it
+    /// can't be detected.
+    pub async fn detect_stream(mut records: St, n: usize, to: Encoding) -> Self {
+        let mut buffered = VecDeque::new();
+        while buffered.len() < n {
+            let Some(record) = futures::StreamExt::next(&mut records).await else {
+                break;
+            };
+            buffered.push_back(record.map(|record| record.to_owned()));
+        }
+        Self::detected(records, buffered, to)
+    }
+}
+
+impl<B, S, I> Iterator for Reencode<I, S>
+where
+    B: RecordData,
+    I: Iterator<Item = Result<Record<B, S>, Error>>,
+{
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(record) = self.next_buffered() {
+            return Some(record);
+        }
+        let record = self.records.next()?;
+        Some(record.map(|record| self.converter.convert_record(&record)))
+    }
+}
+
+impl<B, S, St> AsyncIterator for Reencode<St, S>
+where
+    B: RecordData,
+    St: AsyncIterator<Item = Result<Record<B, S>, Error>> + Unpin,
+{
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        if let Some(record) = this.next_buffered() {
+            return Poll::Ready(Some(record));
+        }
+        let record = ready!(Pin::new(&mut this.records).poll_next(cx));
+        Poll::Ready(
+            record.map(|record| record.map(|record| this.converter.convert_record(&record))),
+        )
+    }
+}
+
+/// Maps each quality score to the representative score of its bin
+///
+/// ```
+/// use bio_streams::quality::Binning;
+/// use bio_streams::record::Phred;
+///
+/// let mut quality = b"#+5?I".to_vec();
+/// let phreds = Phred::from_bytes_mut(&mut quality).unwrap();
+/// Binning::novaseq().apply(phreds);
+/// assert_eq!(quality, b"#-88F");
+/// ```
+#[derive(Clone)]
+pub struct Binning {
+    // binned Phred+33 character for every byte, out of range bytes are kept
+    table: [u8; 256],
+}
+
+impl Binning {
+    /// Bin scores with a function from each score to its binned score
+    pub fn from_fn<F: Fn(u8) -> u8>(f: F) -> Self {
+        let mut table = [0; 256];
+        for (b, out) in (0..=u8::MAX).zip(table.iter_mut()) {
+            *out = if (b'!'..=b'~').contains(&b) {
+                u8::from(Phred::from_score(f(b - 33)))
+            } else {
+                b
+            };
+        }
+        Binning { table }
+    }
+
+    /// Bin scores in each range to a single score. Scores outside of every
+    /// range are unchanged.
+    pub fn from_bins(bins: &[(RangeInclusive<u8>, u8)]) -> Self {
+        Self::from_fn(|q| {
+            bins.iter()
+                .find(|(range, _)| range.contains(&q))
+                .map_or(q, |&(_, binned)| binned)
+        })
+    }
+
+    /// Illumina's 8 level binning, as used by `HiSeq` and `NextSeq`
+    pub fn illumina() -> Self {
+        Self::from_bins(&[
+            (2..=9, 6),
+            (10..=19, 15),
+            (20..=24, 22),
+            (25..=29, 27),
+            (30..=34, 33),
+            (35..=39, 37),
+            (40..=Phred::MAX_SCORE, 40),
+        ])
+    }
+
+    /// The 4 level binning of `NovaSeq` instruments
+    pub fn novaseq() -> Self {
+        Self::from_bins(&[
+            (0..=2, 2),
+            (3..=14, 12),
+            (15..=30, 23),
+            (31..=Phred::MAX_SCORE, 37),
+        ])
+    }
+
+    pub fn bin(&self, q: Phred) -> Phred {
+        Phred::from(self.table[usize::from(u8::from(q))])
+    }
+
+    /// Bin a slice of scores in place
+    pub fn apply(&self, quality: &mut [Phred]) {
+        for q in quality {
+            *q = self.bin(*q);
+        }
+    }
+
+    /// Bin a Phred+33 quality string in place. Characters outside of the
+    /// Phred+33 range are unchanged.
+    pub fn apply_bytes(&self, quality: &mut [u8]) {
+        for b in quality {
+            *b = self.table[usize::from(*b)];
+        }
+    }
+
+    /// An owned copy of a record with its qualities binned
+    pub fn bin_record<B: RecordData, S>(&self, record: &Record<B, S>) -> Record<Vec<u8>, S> {
+        let mut record = record.to_owned();
+        if let Some(quality) = &mut record.raw_quality {
+            self.apply_bytes(quality);
+        }
+        record
+    }
+}
+
+/// Iterator and stream adapter which bins the qualities of records, for
+/// example before they are written
+///
+/// ```
+/// use bio_streams::fastq::Fastq;
+/// use bio_streams::quality::{Binned, Binning};
+/// use bio_streams::writer::{FastqWriter, RecordWriter};
+///
+/// let fq: &[u8] = b"@SEQ_ID_1\nACGT\n+\nIIII\n";
+///
+/// let mut writer = FastqWriter::new(Vec::new());
+/// for record in Binned::new(Fastq::<Vec<u8>>::new(fq), Binning::novaseq()) {
+///     writer.write_record(&record.unwrap()).unwrap();
+/// }
+/// assert_eq!(writer.into_inner(), b"@SEQ_ID_1\nACGT\n+\nFFFF\n");
+/// ```
+pub struct Binned<I> {
+    records: I,
+    binning: Binning,
+}
+
+impl<I> Binned<I> {
+    pub fn new(records: I, binning: Binning) -> Self {
+        Binned { records, binning }
+    }
+
+    pub fn into_inner(self) -> I {
+        self.records
+    }
+}
+
+impl<B, S, I> Iterator for Binned<I>
+where
+    B: RecordData,
+    I: Iterator<Item = Result<Record<B, S>, Error>>,
+{
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.records.next()?;
+        Some(record.map(|record| self.binning.bin_record(&record)))
+    }
+}
+
+impl<B, S, St> AsyncIterator for Binned<St>
+where
+    B: RecordData,
+    St: AsyncIterator<Item = Result<Record<B, S>, Error>> + Unpin,
+{
+    type Item = Result<Record<Vec<u8>, S>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let record = ready!(Pin::new(&mut this.records).poll_next(cx));
+        Poll::Ready(record.map(|record| record.map(|record| this.binning.bin_record(&record))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fastq::{AsyncFastqReader, Fastq, FastqReader};
+    use crate::writer::RecordSink;
+    use futures::executor::block_on;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_decode_encode() {
+        assert_eq!(Encoding::Sanger.decode(b'I'), 40);
+        assert_eq!(Encoding::Illumina.decode(b'h'), 40);
+        assert_eq!(Encoding::Solexa.decode(b'h'), 40);
+        assert_eq!(Encoding::Solexa.decode(b'@'), 3);
+        assert_eq!(Encoding::Solexa.decode(b';'), 1);
+        assert_eq!(Encoding::Solexa.decode(b'J'), 10);
+
+        // out of range characters saturate
+        assert_eq!(Encoding::Illumina.decode(b'5'), 0);
+        assert_eq!(Encoding::Solexa.decode(b'\0'), 1);
+        assert_eq!(Encoding::Sanger.decode(0xff), 93);
+
+        assert_eq!(Encoding::Sanger.encode(40), b'I');
+        assert_eq!(Encoding::Illumina.encode(40), b'h');
+        assert_eq!(Encoding::Illumina.encode(93), b'~');
+        assert_eq!(Encoding::Solexa.encode(0), b';');
+        assert_eq!(Encoding::Solexa.encode(3), b'@');
+        assert_eq!(Encoding::Solexa.encode(40), b'h');
+
+        // Phred encodings round trip
+        for q in 0..=62 {
+            for encoding in [Encoding::Sanger, Encoding::Illumina] {
+                assert_eq!(encoding.decode(encoding.encode(q)), q);
+            }
+        }
+        for q in 10..=62 {
+            assert_eq!(Encoding::Solexa.decode(Encoding::Solexa.encode(q)), q);
+        }
+
+        assert!(Encoding::Sanger.is_valid(b'!'));
+        assert!(!Encoding::Illumina.is_valid(b'!'));
+        assert!(Encoding::Solexa.is_valid(b';'));
+        assert!(!Encoding::Sanger.is_valid(b' '));
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Encoding::detect([&b"II#I"[..], b"5555"]),
+            Some(Encoding::Sanger)
+        );
+        assert_eq!(Encoding::detect([b"IIJJ"]), Some(Encoding::Sanger));
+        assert_eq!(
+            Encoding::detect([&b"hhhh"[..], b"BBTT"]),
+            Some(Encoding::Illumina)
+        );
+        assert_eq!(
+            Encoding::detect([&b"hhhh"[..], b";;TT"]),
+            Some(Encoding::Solexa)
+        );
+        assert_eq!(Encoding::detect(Vec::<Vec<u8>>::new()), None);
+        assert_eq!(Encoding::detect([b"II I"]), None);
+    }
+
+    #[test]
+    fn test_converter() {
+        let converter = Converter::new(Encoding::Illumina, Encoding::Sanger);
+        let mut quality = b"hhB@~".to_vec();
+        converter.convert(&mut quality);
+        assert_eq!(quality, b"II#!_");
+
+        // scores above Phred+64's range saturate
+        let converter = Converter::new(Encoding::Sanger, Encoding::Illumina);
+        let mut quality = b"I#!~".to_vec();
+        converter.convert(&mut quality);
+        assert_eq!(quality, b"hB@~");
+
+        let converter = Converter::new(Encoding::Solexa, Encoding::Sanger);
+        let mut quality = b";@h".to_vec();
+        converter.convert(&mut quality);
+        assert_eq!(quality, b"\"$I");
+    }
+
+    #[test]
+    fn test_reencode() {
+        let fq = b"@r1\nACGT\n+\nhhBB\n@r2\nACGT\n+\nTTTT\n@r3\nAC\n+\nhh\n";
+
+        let records: Vec<Record<Vec<u8>>> =
+            Reencode::detect(Fastq::<Vec<u8>>::new(fq), 2, Encoding::Sanger)
+                .map(Result::unwrap)
+                .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].raw_quality.as_deref(), Some(&b"II##"[..]));
+        assert_eq!(records[1].raw_quality.as_deref(), Some(&b"5555"[..]));
+        assert_eq!(records[2].raw_quality.as_deref(), Some(&b"II"[..]));
+
+        // errors are passed through
+        let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(&fq[..40]));
+        let results: Vec<_> =
+            Reencode::new(&mut reader, Encoding::Illumina, Encoding::Sanger).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_reencode_stream() {
+        let fq = b"@r1\nACGT\n+\nhhBB\n@r2\nACGT\n+\nTTTT\n@r3\nAC\n+\nhh\n";
+
+        let reader: AsyncFastqReader<_> = AsyncFastqReader::new(futures::io::Cursor::new(fq));
+        let records = block_on(Reencode::detect_stream(reader, 2, Encoding::Sanger));
+        assert_eq!(records.from(), Encoding::Illumina);
+
+        let records: Vec<Record<Vec<u8>>> = block_on(futures::StreamExt::collect(
+            futures::StreamExt::map(records, Result::unwrap),
+        ));
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].raw_quality.as_deref(), Some(&b"II##"[..]));
+        assert_eq!(records[1].raw_quality.as_deref(), Some(&b"5555"[..]));
+        assert_eq!(records[2].raw_quality.as_deref(), Some(&b"II"[..]));
+
+        // streams shorter than the detection window
+        let reader: AsyncFastqReader<_> =
+            AsyncFastqReader::new(futures::io::Cursor::new(&fq[..32]));
+        let records = block_on(Reencode::detect_stream(reader, 100, Encoding::Sanger));
+        assert_eq!(records.from(), Encoding::Illumina);
+        let records: Vec<_> = block_on(futures::StreamExt::collect(records));
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn test_binning() {
+        let scores: Vec<u8> = (0..=45).collect();
+        let binned = |binning: &Binning| -> Vec<u8> {
+            scores
+                .iter()
+                .map(|&q| binning.bin(Phred::from_score(q)).score())
+                .collect()
+        };
+
+        let illumina = binned(&Binning::illumina());
+        assert_eq!(&illumina[..3], [0, 1, 6]);
+        assert_eq!(illumina[19], 15);
+        assert_eq!(illumina[20], 22);
+        assert_eq!(illumina[34], 33);
+        assert_eq!(&illumina[39..], [37, 40, 40, 40, 40, 40, 40]);
+
+        let novaseq = binned(&Binning::novaseq());
+        let mut levels = novaseq.clone();
+        levels.dedup();
+        assert_eq!(levels, [2, 12, 23, 37]);
+        assert_eq!(novaseq[14], 12);
+        assert_eq!(novaseq[15], 23);
+        assert_eq!(novaseq[31], 37);
+
+        // user defined bins and functions
+        let custom = Binning::from_bins(&[(0..=19, 10), (20..=200, 30)]);
+        assert_eq!(binned(&custom)[..21], [[10; 20].as_slice(), &[30]].concat());
+        let halved = Binning::from_fn(|q| q / 2);
+        assert_eq!(halved.bin(Phred::from_score(41)).score(), 20);
+
+        // out of range characters are kept
+        let mut quality = b"I I\n".to_vec();
+        Binning::novaseq().apply_bytes(&mut quality);
+        assert_eq!(quality, b"F F\n");
+    }
+
+    #[test]
+    fn test_binned_records() {
+        let fq = b"@r1\nACGT\n+\n#+5I\n@r2\nAC\n+\nI\n";
+
+        let results: Vec<_> = Binned::new(Fastq::<Vec<u8>>::new(fq), Binning::illumina()).collect();
+        assert_eq!(results.len(), 2);
+        let record = results[0].as_ref().unwrap();
+        assert_eq!(record.raw_quality.as_deref(), Some(&b"'07I"[..]));
+        assert!(results[1].is_err());
+
+        let mut record: Record<Vec<u8>> =
+            Record::new(b"r1".to_vec(), b"ACGT".to_vec(), Some(b"#+5I".to_vec()));
+        Binning::novaseq().apply(record.quality_mut().unwrap());
+        assert_eq!(record.raw_quality.as_deref(), Some(&b"#-8F"[..]));
+
+        let mut record: Record<Vec<u8>> = Record::new(b"r1".to_vec(), b"AC".to_vec(), None);
+        assert!(record.quality_mut().is_err());
+    }
+
+    #[test]
+    fn test_binned_stream() {
+        let fq = b"@r1\nACGT\n+\n#+5I\n@r2\nAC\n+\n?I\n";
+
+        let reader: AsyncFastqReader<_> = AsyncFastqReader::new(futures::io::Cursor::new(fq));
+        let binned = Binned::new(reader, Binning::novaseq());
+
+        let mut sink = RecordSink::fastq(Vec::new());
+        block_on(futures::StreamExt::forward(
+            futures::TryStreamExt::err_into(binned),
+            &mut sink,
+        ))
+        .unwrap();
+        assert_eq!(sink.into_inner(), b"@r1\nACGT\n+\n#-8F\n@r2\nAC\n+\n8F\n");
+
+        // parse errors end the stream and are returned by the sink
+        let reader: AsyncFastqReader<_> =
+            AsyncFastqReader::new(futures::io::Cursor::new(&fq[..20]));
+        let mut sink = RecordSink::fastq(Vec::new());
+        let result = block_on(futures::StreamExt::forward(
+            futures::TryStreamExt::err_into(Binned::new(reader, Binning::novaseq())),
+            &mut sink,
+        ));
+        assert!(result.is_err());
+    }
+}
@@ -1,19 +1,69 @@
+use std::borrow::Cow;
+use std::fmt;
 use std::marker::PhantomData;
 
 pub use crate::error::ParseError;
 
+/// A Phred+33 encoded quality score. See `quality::Encoding` for other
+/// encodings.
 #[derive(Debug, PartialEq, Copy, Clone)]
 #[repr(transparent)]
 pub struct Phred(u8);
 
 impl Phred {
-    fn to_prob(self) -> f64 {
-        let q = f64::from(self.0 - 33);
+    /// Highest score that can be Phred+33 encoded, as `~`
+    pub const MAX_SCORE: u8 = 93;
+
+    /// The quality score, saturating at 0 for characters below `!`
+    pub fn score(self) -> u8 {
+        self.0.saturating_sub(33)
+    }
+
+    /// Encode a quality score, saturating at `MAX_SCORE`
+    pub fn from_score(q: u8) -> Self {
+        Phred(q.min(Self::MAX_SCORE) + 33)
+    }
+
+    /// The probability that the base call is wrong
+    pub fn to_prob(self) -> f64 {
+        let q = f64::from(self.score());
         10_f64.powf(-q / 10.0)
     }
 
+    // `as` saturates: NaN and negative scores become 0 and a probability of 0
+    // gives the highest score
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     fn from_prob(p: f64) -> Self {
-        unsafe { Phred((-10_f64 * p.log10()).to_int_unchecked::<u8>() + 33) }
+        Phred::from_score((-10_f64 * p.log10()) as u8)
+    }
+
+    /// Reinterpret a Phred+33 encoded quality string as a slice of `Phred`s
+    ///
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if any character is outside of the
+    /// printable range `!`..=`~`
+    pub fn from_bytes(raw: &[u8]) -> Result<&[Phred], ParseError> {
+        if raw.iter().any(|b| !(b'!'..=b'~').contains(b)) {
+            return Err(ParseError::InvalidQuality);
+        }
+
+        // Safety: `Phred` is `repr(transparent)` over `u8`
+        Ok(unsafe { &*(std::ptr::from_ref::<[u8]>(raw) as *const [Phred]) })
+    }
+
+    /// Reinterpret a Phred+33 encoded quality string as a mutable slice of
+    /// `Phred`s
+    ///
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if any character is outside of the
+    /// printable range `!`..=`~`
+    pub fn from_bytes_mut(raw: &mut [u8]) -> Result<&mut [Phred], ParseError> {
+        if raw.iter().any(|b| !(b'!'..=b'~').contains(b)) {
+            return Err(ParseError::InvalidQuality);
+        }
+
+        // Safety: `Phred` is `repr(transparent)` over `u8`
+        Ok(unsafe { &mut *(std::ptr::from_mut::<[u8]>(raw) as *mut [Phred]) })
     }
 }
 
@@ -53,40 +103,256 @@ impl RecordData for Vec<u8> {
     type Buf = Self;
 }
 
-pub struct Record<B: RecordData, S: TryFrom<B::Buf> = B> {
+impl RecordData for Cow<'_, [u8]> {
+    type Buf = Self;
+}
+
+pub struct Record<B: RecordData, S = B> {
     pub(crate) raw_fields: B::Buf,
     pub(crate) raw_seq: B::Buf,
     pub(crate) raw_quality: Option<B::Buf>,
     pub(crate) _p: PhantomData<S>,
 }
 
-impl<'a, S: TryFrom<&'a [u8], Error = ParseError>> Record<&'a [u8], S> {
+impl<B: RecordData, S> Record<B, S> {
+    pub fn new(fields: B::Buf, seq: B::Buf, quality: Option<B::Buf>) -> Self {
+        Record {
+            raw_fields: fields,
+            raw_seq: seq,
+            raw_quality: quality,
+            _p: PhantomData,
+        }
+    }
+
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if the record has no quality
+    /// string or it contains characters outside of the Phred+33 range
+    pub fn quality(&self) -> Result<&[Phred], ParseError> {
+        match &self.raw_quality {
+            None => Err(ParseError::InvalidQuality),
+            Some(raw) => Phred::from_bytes(raw.as_ref()),
+        }
+    }
+
+    /// The mean quality score, or `None` for an empty quality string
+    ///
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if the record has no quality
+    /// string or it contains characters outside of the Phred+33 range
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean_quality(&self) -> Result<Option<f64>, ParseError> {
+        let quality = self.quality()?;
+        if quality.is_empty() {
+            return Ok(None);
+        }
+        let total: u64 = quality.iter().map(|q| u64::from(q.score())).sum();
+        Ok(Some(total as f64 / quality.len() as f64))
+    }
+
+    /// The median quality score, the mean of the two middle scores for an
+    /// even length, or `None` for an empty quality string
+    ///
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if the record has no quality
+    /// string or it contains characters outside of the Phred+33 range
+    pub fn median_quality(&self) -> Result<Option<f64>, ParseError> {
+        let quality = self.quality()?;
+        if quality.is_empty() {
+            return Ok(None);
+        }
+
+        // scores are bounded, so count them rather than sorting a copy
+        let mut counts = [0usize; Phred::MAX_SCORE as usize + 1];
+        for q in quality {
+            counts[usize::from(q.score())] += 1;
+        }
+        let nth = |n: usize| {
+            let mut seen = 0;
+            (0..=Phred::MAX_SCORE)
+                .zip(counts)
+                .find(|&(_, count)| {
+                    seen += count;
+                    seen > n
+                })
+                .map_or(0, |(q, _)| q)
+        };
+
+        let mid = quality.len() / 2;
+        let median = if quality.len() % 2 == 1 {
+            f64::from(nth(mid))
+        } else {
+            f64::midpoint(f64::from(nth(mid - 1)), f64::from(nth(mid)))
+        };
+        Ok(Some(median))
+    }
+
+    /// The lowest quality score, or `None` for an empty quality string
+    ///
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if the record has no quality
+    /// string or it contains characters outside of the Phred+33 range
+    pub fn min_quality(&self) -> Result<Option<u8>, ParseError> {
+        Ok(self.quality()?.iter().map(|q| q.score()).min())
+    }
+
+    /// The expected number of errors in the read, the sum of the error
+    /// probabilities of its bases
+    ///
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if the record has no quality
+    /// string or it contains characters outside of the Phred+33 range
+    pub fn expected_errors(&self) -> Result<f64, ParseError> {
+        Ok(self.quality()?.iter().map(|q| q.to_prob()).sum())
+    }
+
+    /// The fraction of bases with a quality score of at least `threshold`,
+    /// or `None` for an empty quality string
+    ///
+    /// # Errors
+    /// Returns `ParseError::InvalidQuality` if the record has no quality
+    /// string or it contains characters outside of the Phred+33 range
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fraction_at_least(&self, threshold: u8) -> Result<Option<f64>, ParseError> {
+        let quality = self.quality()?;
+        if quality.is_empty() {
+            return Ok(None);
+        }
+        let passing = quality.iter().filter(|q| q.score() >= threshold).count();
+        Ok(Some(passing as f64 / quality.len() as f64))
+    }
+
+    /// Copy the record's data into an owned record
+    pub fn to_owned(&self) -> Record<Vec<u8>, S> {
+        Record {
+            raw_fields: self.raw_fields.as_ref().to_vec(),
+            raw_seq: self.raw_seq.as_ref().to_vec(),
+            raw_quality: self.raw_quality.as_ref().map(|q| q.as_ref().to_vec()),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<'a, S> Record<&'a [u8], S> {
     pub fn fields(&self) -> &'a [u8] {
         self.raw_fields
     }
+}
 
+impl<'a, E: Into<ParseError>, S: TryFrom<&'a [u8], Error = E>> Record<&'a [u8], S> {
     /// # Errors
     /// Parsing into the target sequence type may fail on bad characters
     pub fn seq(&self) -> Result<S, ParseError> {
-        S::try_from(self.raw_seq)
+        S::try_from(self.raw_seq).map_err(Into::into)
+    }
+}
+
+impl<S> Record<Vec<u8>, S> {
+    pub fn fields(&self) -> &[u8] {
+        &self.raw_fields
     }
 
     /// # Errors
-    /// Parsing phred qualities may be fallible
-    pub fn quality(&self) -> Result<&[Phred], ParseError> {
-        match self.raw_quality {
+    /// Returns `ParseError::InvalidQuality` if the record has no quality
+    /// string or it contains characters outside of the Phred+33 range
+    pub fn quality_mut(&mut self) -> Result<&mut [Phred], ParseError> {
+        match &mut self.raw_quality {
             None => Err(ParseError::InvalidQuality),
-            Some(_) => unimplemented!(),
+            Some(raw) => Phred::from_bytes_mut(raw),
         }
     }
 }
 
+impl<E: Into<ParseError>, S: TryFrom<Vec<u8>, Error = E>> Record<Vec<u8>, S> {
+    /// # Errors
+    /// Parsing into the target sequence type may fail on bad characters
+    pub fn seq(&self) -> Result<S, ParseError> {
+        S::try_from(self.raw_seq.clone()).map_err(Into::into)
+    }
+
+    /// Consume the record, parsing its sequence without copying
+    ///
+    /// # Errors
+    /// Parsing into the target sequence type may fail on bad characters
+    pub fn into_seq(self) -> Result<S, ParseError> {
+        S::try_from(self.raw_seq).map_err(Into::into)
+    }
+}
+
+impl<S> Record<Cow<'_, [u8]>, S> {
+    pub fn fields(&self) -> &[u8] {
+        &self.raw_fields
+    }
+}
+
+impl<E: Into<ParseError>, S: for<'b> TryFrom<&'b [u8], Error = E>> Record<Cow<'_, [u8]>, S> {
+    /// # Errors
+    /// Parsing into the target sequence type may fail on bad characters
+    pub fn seq(&self) -> Result<S, ParseError> {
+        S::try_from(&self.raw_seq).map_err(Into::into)
+    }
+}
+
+impl<B: RecordData, S> Clone for Record<B, S>
+where
+    B::Buf: Clone,
+{
+    fn clone(&self) -> Self {
+        Record {
+            raw_fields: self.raw_fields.clone(),
+            raw_seq: self.raw_seq.clone(),
+            raw_quality: self.raw_quality.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+/// An empty record, e.g. to be filled by `FastqReader::read_record`
+impl<B: RecordData, S> Default for Record<B, S>
+where
+    B::Buf: Default,
+{
+    fn default() -> Self {
+        Record::new(B::Buf::default(), B::Buf::default(), None)
+    }
+}
+
+impl<B: RecordData, S> PartialEq for Record<B, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw_fields.as_ref() == other.raw_fields.as_ref()
+            && self.raw_seq.as_ref() == other.raw_seq.as_ref()
+            && self.raw_quality.as_ref().map(AsRef::as_ref)
+                == other.raw_quality.as_ref().map(AsRef::as_ref)
+    }
+}
+
+impl<B: RecordData, S> Eq for Record<B, S> {}
+
+impl<B: RecordData, S> fmt::Debug for Record<B, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("fields", &String::from_utf8_lossy(self.raw_fields.as_ref()))
+            .field("seq", &String::from_utf8_lossy(self.raw_seq.as_ref()))
+            .field(
+                "quality",
+                &self
+                    .raw_quality
+                    .as_ref()
+                    .map(|q| String::from_utf8_lossy(q.as_ref())),
+            )
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Phred;
+    use super::{ParseError, Phred, Record};
+    use crate::fastq::{Fastq, FastqReader};
+    use bio_seq::prelude::*;
+    use std::io::Cursor;
+    use std::marker::PhantomData;
 
     fn assert_eqf(x: f64, y: f64) {
-        assert!((x - y).abs() < 0.0001)
+        assert!((x - y).abs() < 0.0001);
     }
 
     #[test]
@@ -116,4 +382,143 @@ mod tests {
         assert_eq!(Phred(b'E'), Phred::from(0.0002));
         assert_eq!(Phred(b'I'), Phred::from(0.0001));
     }
+
+    #[test]
+    fn saturating_phred() {
+        assert_eq!(Phred::from(0.0), Phred(b'~'));
+        assert_eq!(Phred::from(1e-20), Phred(b'~'));
+        assert_eq!(Phred::from(2.0), Phred(b'!'));
+        assert_eq!(Phred::from(-1.0), Phred(b'!'));
+        assert_eq!(Phred::from(f64::NAN), Phred(b'!'));
+
+        assert_eq!(Phred::from_score(40), Phred(b'I'));
+        assert_eq!(Phred::from_score(200), Phred(b'~'));
+        assert_eq!(Phred(b'I').score(), 40);
+        assert_eq!(Phred(b' ').score(), 0);
+        assert_eqf(Phred(b'\0').to_prob(), 1.0);
+    }
+
+    #[test]
+    fn record_quality() {
+        let record: Record<&[u8]> = Record {
+            raw_fields: b"SEQ_ID_1",
+            raw_seq: b"ACGT",
+            raw_quality: Some(b"!5I~"),
+            _p: PhantomData,
+        };
+        assert_eq!(
+            record.quality().unwrap(),
+            &[Phred(b'!'), Phred(b'5'), Phred(b'I'), Phred(b'~')]
+        );
+
+        let record: Record<Vec<u8>> = Record {
+            raw_fields: b"SEQ_ID_2".to_vec(),
+            raw_seq: b"ACGT".to_vec(),
+            raw_quality: Some(b"AB C".to_vec()),
+            _p: PhantomData,
+        };
+        assert!(matches!(record.quality(), Err(ParseError::InvalidQuality)));
+
+        let record: Record<Vec<u8>> = Record {
+            raw_fields: b"SEQ_ID_3".to_vec(),
+            raw_seq: b"ACGT".to_vec(),
+            raw_quality: None,
+            _p: PhantomData,
+        };
+        assert!(matches!(record.quality(), Err(ParseError::InvalidQuality)));
+    }
+
+    fn with_quality(quality: &[u8]) -> Record<&[u8]> {
+        Record::new(&b"read"[..], &b"ACGTACGT"[..quality.len()], Some(quality))
+    }
+
+    #[test]
+    fn quality_stats() {
+        // scores 40, 20, 10, 30, 2
+        let record: Record<&[u8]> = with_quality(b"I5+?#");
+        assert_eqf(record.mean_quality().unwrap().unwrap(), 20.4);
+        assert_eqf(record.median_quality().unwrap().unwrap(), 20.0);
+        assert_eq!(record.min_quality().unwrap(), Some(2));
+        assert_eqf(
+            record.expected_errors().unwrap(),
+            0.0001 + 0.01 + 0.1 + 0.001 + 0.630_957,
+        );
+        assert_eqf(record.fraction_at_least(20).unwrap().unwrap(), 0.6);
+        assert_eqf(record.fraction_at_least(30).unwrap().unwrap(), 0.4);
+        assert_eqf(record.fraction_at_least(41).unwrap().unwrap(), 0.0);
+
+        // even lengths take the mean of the middle scores
+        let record: Record<&[u8]> = with_quality(b"II+5");
+        assert_eqf(record.median_quality().unwrap().unwrap(), 30.0);
+        assert_eqf(record.mean_quality().unwrap().unwrap(), 27.5);
+        assert_eqf(record.expected_errors().unwrap(), 0.1102);
+
+        let record: Record<Vec<u8>> =
+            Record::new(b"r3".to_vec(), b"A".to_vec(), Some(b"~".to_vec()));
+        assert_eqf(record.median_quality().unwrap().unwrap(), 93.0);
+        assert_eq!(record.min_quality().unwrap(), Some(93));
+
+        let empty: Record<&[u8]> = with_quality(b"");
+        assert_eq!(empty.mean_quality().unwrap(), None);
+        assert_eq!(empty.median_quality().unwrap(), None);
+        assert_eq!(empty.min_quality().unwrap(), None);
+        assert_eq!(empty.fraction_at_least(20).unwrap(), None);
+        assert_eqf(empty.expected_errors().unwrap(), 0.0);
+
+        let fasta: Record<&[u8]> = Record::new(&b"r5"[..], &b"ACGT"[..], None);
+        assert!(matches!(
+            fasta.mean_quality(),
+            Err(ParseError::InvalidQuality)
+        ));
+        assert!(fasta.expected_errors().is_err());
+    }
+
+    #[test]
+    fn owned_record_accessors() {
+        let fq = b"@SEQ_ID_1\nACGTTGCA\n+\nIIII!!!!\n";
+
+        let mut reader: FastqReader<Cursor<&[u8]>, Seq<Dna>> = FastqReader::new(Cursor::new(fq));
+        let record = (&mut reader).next().unwrap().unwrap();
+
+        assert_eq!(record.fields(), b"SEQ_ID_1");
+        assert_eq!(
+            record.seq().unwrap(),
+            Seq::<Dna>::from_str("ACGTTGCA").unwrap()
+        );
+        assert_eq!(record.quality().unwrap()[..4], [Phred(b'I'); 4]);
+        assert_eq!(record.quality().unwrap()[4..], [Phred(b'!'); 4]);
+
+        let built: Record<Vec<u8>, Seq<Dna>> = Record::new(
+            b"SEQ_ID_1".to_vec(),
+            b"ACGTTGCA".to_vec(),
+            Some(b"IIII!!!!".to_vec()),
+        );
+        assert_eq!(record, built);
+        assert_eq!(record.clone(), built);
+        assert_eq!(
+            built.into_seq().unwrap(),
+            Seq::<Dna>::from_str("ACGTTGCA").unwrap()
+        );
+
+        let bad: Record<Vec<u8>, Seq<Dna>> = Record::new(b"X".to_vec(), b"ACGU".to_vec(), None);
+        assert!(matches!(bad.seq(), Err(ParseError::InvalidSequence(_))));
+    }
+
+    #[test]
+    fn borrowed_to_owned() {
+        let fq = b"@SEQ_ID_1\nACGTTGCA\n+\nIIII!!!!\n";
+
+        let borrowed: Record<&[u8], Seq<Dna>> = Fastq::new(fq).next().unwrap().unwrap();
+        let owned: Record<Vec<u8>, Seq<Dna>> = borrowed.to_owned();
+
+        assert_eq!(owned.fields(), borrowed.fields());
+        assert_eq!(owned.seq().unwrap(), borrowed.seq().unwrap());
+        assert_eq!(owned.quality().unwrap(), borrowed.quality().unwrap());
+        assert_eq!(borrowed.clone(), borrowed);
+
+        assert_eq!(
+            format!("{owned:?}"),
+            r#"Record { fields: "SEQ_ID_1", seq: "ACGTTGCA", quality: Some("IIII!!!!") }"#
+        );
+    }
 }
@@ -0,0 +1,167 @@
+//! Newline and record boundary scanning
+//!
+//! With the `memchr` feature newlines are found with vectorised search,
+//! otherwise one byte at a time. Both give identical results.
+
+/// Position of the first newline in `haystack`, checking one byte at a time
+pub fn find_newline_scalar(haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b == b'\n')
+}
+
+/// Position of the first newline in `haystack`
+#[cfg(feature = "memchr")]
+pub fn find_newline(haystack: &[u8]) -> Option<usize> {
+    memchr::memchr(b'\n', haystack)
+}
+
+/// Position of the first newline in `haystack`
+#[cfg(not(feature = "memchr"))]
+pub fn find_newline(haystack: &[u8]) -> Option<usize> {
+    find_newline_scalar(haystack)
+}
+
+/// Strip a trailing `\n` or `\r\n` from a line
+pub(crate) fn trim_eol(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Number of newlines in `haystack`
+#[cfg(feature = "memchr")]
+pub fn count_newlines(haystack: &[u8]) -> usize {
+    memchr::memchr_iter(b'\n', haystack).count()
+}
+
+/// Number of newlines in `haystack`
+#[cfg(not(feature = "memchr"))]
+pub fn count_newlines(haystack: &[u8]) -> usize {
+    count_newlines_scalar(haystack)
+}
+
+/// Number of newlines in `haystack`, checking one byte at a time
+#[allow(clippy::naive_bytecount)]
+pub fn count_newlines_scalar(haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == b'\n').count()
+}
+
+/// Find the newlines ending each line of up to `max` complete four line
+/// records in `buf`, starting from `start`, and append them to `records`.
+/// Returns the offset following the last complete record.
+///
+/// All of the newlines are found in a single pass over the input.
+#[cfg(feature = "memchr")]
+pub fn fastq_records(buf: &[u8], start: usize, max: usize, records: &mut Vec<[usize; 4]>) -> usize {
+    let mut newlines = memchr::memchr_iter(b'\n', &buf[start..]).map(|n| start + n);
+    let mut end = start;
+
+    for _ in 0..max {
+        let (Some(a), Some(b), Some(c), Some(d)) = (
+            newlines.next(),
+            newlines.next(),
+            newlines.next(),
+            newlines.next(),
+        ) else {
+            break;
+        };
+        records.push([a, b, c, d]);
+        end = d + 1;
+    }
+
+    end
+}
+
+/// Find the newlines ending each line of up to `max` complete four line
+/// records in `buf`, starting from `start`, and append them to `records`.
+/// Returns the offset following the last complete record.
+#[cfg(not(feature = "memchr"))]
+pub fn fastq_records(buf: &[u8], start: usize, max: usize, records: &mut Vec<[usize; 4]>) -> usize {
+    fastq_records_scalar(buf, start, max, records)
+}
+
+/// `fastq_records`, checking one byte at a time
+pub fn fastq_records_scalar(
+    buf: &[u8],
+    start: usize,
+    max: usize,
+    records: &mut Vec<[usize; 4]>,
+) -> usize {
+    let mut end = start;
+
+    'records: for _ in 0..max {
+        let mut record = [0; 4];
+        let mut pos = end;
+        for newline in &mut record {
+            let Some(n) = find_newline_scalar(&buf[pos..]) else {
+                break 'records;
+            };
+            *newline = pos + n;
+            pos += n + 1;
+        }
+        records.push(record);
+        end = pos;
+    }
+
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // lines of varied lengths, including empty lines and no final newline
+    fn sample() -> Vec<u8> {
+        let mut x: u32 = 0x9e37_79b9;
+        (0..100_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                if x.is_multiple_of(37) {
+                    b'\n'
+                } else {
+                    b"ACGT"[(x % 4) as usize]
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_newline() {
+        let data = sample();
+        let mut pos = 0;
+        while let Some(n) = find_newline_scalar(&data[pos..]) {
+            assert_eq!(find_newline(&data[pos..]), Some(n));
+            pos += n + 1;
+        }
+        assert_eq!(find_newline(&data[pos..]), None);
+        assert_eq!(find_newline(b""), None);
+
+        assert_eq!(count_newlines(&data), count_newlines_scalar(&data));
+        assert_eq!(count_newlines(b"a\nb\n\n"), 3);
+    }
+
+    #[test]
+    fn test_fastq_records() {
+        let data = sample();
+
+        for start in [0, 1, 100, data.len() - 10] {
+            for max in [0, 1, 7, usize::MAX] {
+                let mut fast = Vec::new();
+                let mut scalar = Vec::new();
+                let end = fastq_records(&data, start, max, &mut fast);
+
+                assert_eq!(end, fastq_records_scalar(&data, start, max, &mut scalar));
+                assert_eq!(fast, scalar);
+                assert!(fast.len() <= max);
+                if let Some(last) = fast.last() {
+                    assert_eq!(end, last[3] + 1);
+                }
+            }
+        }
+
+        let mut records = Vec::new();
+        let fq = b"@r1\nACGT\n+\nIIII\n@r2\nAC\n+\n";
+        assert_eq!(fastq_records(fq, 0, 10, &mut records), 16);
+        assert_eq!(records, [[3, 8, 10, 15]]);
+    }
+}
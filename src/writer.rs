@@ -0,0 +1,605 @@
+use futures::io::AsyncWrite;
+use futures::{ready, Sink};
+use std::io;
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+#[cfg(feature = "tokio")]
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+pub use crate::error::Error;
+use crate::paired::{InterleavedFastq, PairedFastq};
+pub use crate::record::{Record, RecordData};
+
+/// Serialise records into an output format
+pub trait RecordWriter {
+    type Inner: Write;
+
+    /// # Errors
+    /// Propagates errors from the underlying writer, or if the record can't
+    /// be represented in the output format
+    fn write_record<B: RecordData, S>(&mut self, record: &Record<B, S>) -> io::Result<()>;
+
+    fn get_ref(&self) -> &Self::Inner;
+
+    fn get_mut(&mut self) -> &mut Self::Inner;
+
+    /// # Errors
+    /// Propagates errors from the underlying writer
+    fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+}
+
+pub struct FastqWriter<W: Write> {
+    writer: W,
+    repeat_header: bool,
+}
+
+impl<W: Write> FastqWriter<W> {
+    pub fn new(writer: W) -> Self {
+        FastqWriter {
+            writer,
+            repeat_header: false,
+        }
+    }
+
+    /// Repeat the record's header on the `+` separator line
+    #[must_use]
+    pub fn repeat_header(mut self, repeat: bool) -> Self {
+        self.repeat_header = repeat;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RecordWriter for FastqWriter<W> {
+    type Inner = W;
+
+    fn write_record<B: RecordData, S>(&mut self, record: &Record<B, S>) -> io::Result<()> {
+        let Some(quality) = &record.raw_quality else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "FASTQ records require a quality string",
+            ));
+        };
+        if quality.as_ref().len() != record.raw_seq.as_ref().len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "FASTQ sequence and quality lengths differ",
+            ));
+        }
+
+        let fields = record.raw_fields.as_ref();
+
+        self.writer.write_all(b"@")?;
+        self.writer.write_all(fields)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.write_all(record.raw_seq.as_ref())?;
+        self.writer.write_all(b"\n+")?;
+        if self.repeat_header {
+            self.writer.write_all(fields)?;
+        }
+        self.writer.write_all(b"\n")?;
+        self.writer.write_all(quality.as_ref())?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+pub struct FastaWriter<W: Write> {
+    writer: W,
+    line_width: usize,
+}
+
+impl<W: Write> FastaWriter<W> {
+    pub fn new(writer: W) -> Self {
+        FastaWriter {
+            writer,
+            line_width: 0,
+        }
+    }
+
+    /// Wrap sequences to lines of `width` bases. A width of 0 writes each
+    /// sequence on a single line.
+    #[must_use]
+    pub fn line_width(mut self, width: usize) -> Self {
+        self.line_width = width;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> RecordWriter for FastaWriter<W> {
+    type Inner = W;
+
+    fn write_record<B: RecordData, S>(&mut self, record: &Record<B, S>) -> io::Result<()> {
+        self.writer.write_all(b">")?;
+        self.writer.write_all(record.raw_fields.as_ref())?;
+        self.writer.write_all(b"\n")?;
+
+        let seq = record.raw_seq.as_ref();
+        if seq.is_empty() {
+            return Ok(());
+        }
+
+        if self.line_width == 0 {
+            self.writer.write_all(seq)?;
+            return self.writer.write_all(b"\n");
+        }
+
+        for line in seq.chunks(self.line_width) {
+            self.writer.write_all(line)?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+/// Writes pairs of records to a single output, each R1 record followed by
+/// its R2 mate
+pub struct InterleavedWriter<W: RecordWriter> {
+    writer: W,
+}
+
+impl<W: RecordWriter> InterleavedWriter<W> {
+    pub fn new(writer: W) -> Self {
+        InterleavedWriter { writer }
+    }
+
+    /// # Errors
+    /// Propagates errors from the underlying writer
+    pub fn write_pair<B1: RecordData, S1, B2: RecordData, S2>(
+        &mut self,
+        r1: &Record<B1, S1>,
+        r2: &Record<B2, S2>,
+    ) -> io::Result<()> {
+        self.writer.write_record(r1)?;
+        self.writer.write_record(r2)
+    }
+
+    /// Interleave two streams of records, checking mate names as in
+    /// `PairedFastq`. Returns the number of pairs written.
+    ///
+    /// # Errors
+    /// Stops at the first error from either stream, at unpaired or
+    /// mismatched mates, or if writing fails
+    pub fn write_paired<B1, S1, B2, S2, I1, I2>(&mut self, r1: I1, r2: I2) -> Result<usize, Error>
+    where
+        B1: RecordData,
+        B2: RecordData,
+        I1: IntoIterator<Item = Result<Record<B1, S1>, Error>>,
+        I2: IntoIterator<Item = Result<Record<B2, S2>, Error>>,
+    {
+        let mut pairs = 0;
+        for pair in PairedFastq::new(r1.into_iter(), r2.into_iter()) {
+            let (r1, r2) = pair?;
+            self.write_pair(&r1, &r2)?;
+            pairs += 1;
+        }
+        Ok(pairs)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Splits pairs of records between an R1 and an R2 writer
+pub struct Deinterleaver<W1: RecordWriter, W2: RecordWriter> {
+    r1: W1,
+    r2: W2,
+}
+
+impl<W1: RecordWriter, W2: RecordWriter> Deinterleaver<W1, W2> {
+    pub fn new(r1: W1, r2: W2) -> Self {
+        Deinterleaver { r1, r2 }
+    }
+
+    /// # Errors
+    /// Propagates errors from the underlying writers
+    pub fn write_pair<B1: RecordData, S1, B2: RecordData, S2>(
+        &mut self,
+        r1: &Record<B1, S1>,
+        r2: &Record<B2, S2>,
+    ) -> io::Result<()> {
+        self.r1.write_record(r1)?;
+        self.r2.write_record(r2)
+    }
+
+    /// Split a stream of interleaved records, checking mate names as in
+    /// `InterleavedFastq`. Returns the number of pairs written.
+    ///
+    /// # Errors
+    /// Stops at the first error from the stream, at an unpaired or
+    /// mismatched mate, or if writing fails
+    pub fn write_interleaved<B, S, I>(&mut self, records: I) -> Result<usize, Error>
+    where
+        B: RecordData,
+        I: IntoIterator<Item = Result<Record<B, S>, Error>>,
+    {
+        let mut pairs = 0;
+        for pair in InterleavedFastq::new(records.into_iter()) {
+            let (r1, r2) = pair?;
+            self.write_pair(&r1, &r2)?;
+            pairs += 1;
+        }
+        Ok(pairs)
+    }
+
+    /// # Errors
+    /// Propagates errors from the underlying writers
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.r1.flush()?;
+        self.r2.flush()
+    }
+
+    pub fn into_inner(self) -> (W1, W2) {
+        (self.r1, self.r2)
+    }
+}
+
+// flush the buffer to the underlying writer once it is this large
+const SINK_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Asynchronous `Sink` of records that formats them with a `RecordWriter`
+/// into a buffer which is drained into an `AsyncWrite`
+pub struct RecordSink<W: AsyncWrite + Unpin, F: RecordWriter<Inner = Vec<u8>>> {
+    writer: W,
+    format: F,
+    // bytes of the buffer already written
+    pos: usize,
+}
+
+impl<W: AsyncWrite + Unpin> RecordSink<W, FastqWriter<Vec<u8>>> {
+    pub fn fastq(writer: W) -> Self {
+        RecordSink::new(writer, FastqWriter::new(Vec::new()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> RecordSink<W, FastaWriter<Vec<u8>>> {
+    pub fn fasta(writer: W) -> Self {
+        RecordSink::new(writer, FastaWriter::new(Vec::new()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin, F: RecordWriter<Inner = Vec<u8>>> RecordSink<W, F> {
+    pub fn new(writer: W, format: F) -> Self {
+        RecordSink {
+            writer,
+            format,
+            pos: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn poll_write_buffer(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let buffer = self.format.get_mut();
+
+        while self.pos < buffer.len() {
+            let n = ready!(Pin::new(&mut self.writer).poll_write(cx, &buffer[self.pos..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pos += n;
+        }
+
+        buffer.clear();
+        self.pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W: tokio::io::AsyncWrite + Unpin, F: RecordWriter<Inner = Vec<u8>>> RecordSink<Compat<W>, F> {
+    /// Write records to a `tokio` writer
+    pub fn from_tokio(writer: W, format: F) -> Self {
+        RecordSink::new(writer.compat_write(), format)
+    }
+}
+
+impl<W, F, B, S> Sink<Record<B, S>> for RecordSink<W, F>
+where
+    W: AsyncWrite + Unpin,
+    F: RecordWriter<Inner = Vec<u8>> + Unpin,
+    B: RecordData,
+{
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let sink = self.get_mut();
+
+        if sink.format.get_ref().len() >= SINK_BUFFER_SIZE {
+            sink.poll_write_buffer(cx)
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, record: Record<B, S>) -> io::Result<()> {
+        self.get_mut().format.write_record(&record)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let sink = self.get_mut();
+
+        ready!(sink.poll_write_buffer(cx))?;
+        Pin::new(&mut sink.writer).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let sink = self.get_mut();
+
+        ready!(sink.poll_write_buffer(cx))?;
+        Pin::new(&mut sink.writer).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fasta::FastaReader;
+    use crate::fastq::{Fastq, FastqReader};
+    use futures::executor::block_on;
+    use futures::SinkExt;
+    use std::io::Cursor;
+    use std::marker::PhantomData;
+
+    const FQ1: &[u8] = b"@SEQ_ID_1
+ACTCGATCGCGACGAA
++
+AFFFFFFFFFFFFEBA
+@SEQ_ID_2
+CATCGACTACGGCG
++
+GGGGGGGGGGGGGG\n";
+
+    #[test]
+    fn test_fastq_writer_borrowed() {
+        let mut writer = FastqWriter::new(Vec::new());
+
+        for record in Fastq::<Vec<u8>>::new(FQ1) {
+            writer.write_record(&record.unwrap()).unwrap();
+        }
+
+        assert_eq!(writer.into_inner(), FQ1);
+    }
+
+    #[test]
+    fn test_fastq_writer_owned() {
+        let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(FQ1));
+        let mut writer = FastqWriter::new(Vec::new()).repeat_header(true);
+
+        for record in &mut reader {
+            writer.write_record(&record.unwrap()).unwrap();
+        }
+
+        assert_eq!(
+            writer.into_inner(),
+            b"@SEQ_ID_1
+ACTCGATCGCGACGAA
++SEQ_ID_1
+AFFFFFFFFFFFFEBA
+@SEQ_ID_2
+CATCGACTACGGCG
++SEQ_ID_2
+GGGGGGGGGGGGGG\n"
+        );
+    }
+
+    #[test]
+    fn test_fastq_writer_no_quality() {
+        let record: Record<&[u8]> = Record {
+            raw_fields: b"SEQ_ID_1",
+            raw_seq: b"ACGT",
+            raw_quality: None,
+            _p: PhantomData,
+        };
+
+        let mut writer = FastqWriter::new(Vec::new());
+        assert!(writer.write_record(&record).is_err());
+    }
+
+    #[test]
+    fn test_fastq_writer_mismatched_lengths() {
+        let record: Record<&[u8]> = Record {
+            raw_fields: b"SEQ_ID_1",
+            raw_seq: b"ACGT",
+            raw_quality: Some(b"III"),
+            _p: PhantomData,
+        };
+
+        let mut writer = FastqWriter::new(Vec::new());
+        let e = writer.write_record(&record).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert!(writer.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_fasta_writer() {
+        let data = b">SEQ_ID_1
+ACTCGATCGCGACG
+ACACGATCGCGCGC
+CATCGACTAC
+>SEQ_ID_2
+GGGGGGGGGGGGGG\n";
+
+        let mut writer = FastaWriter::new(Vec::new()).line_width(10);
+        for record in &mut FastaReader::<Cursor<&[u8]>>::new(Cursor::new(data)) {
+            writer.write_record(&record.unwrap()).unwrap();
+        }
+
+        assert_eq!(
+            writer.into_inner(),
+            b">SEQ_ID_1
+ACTCGATCGC
+GACGACACGA
+TCGCGCGCCA
+TCGACTAC
+>SEQ_ID_2
+GGGGGGGGGG
+GGGG\n"
+        );
+
+        let mut writer = FastaWriter::new(Vec::new());
+        for record in Fastq::<Vec<u8>>::new(FQ1) {
+            writer.write_record(&record.unwrap()).unwrap();
+        }
+
+        assert_eq!(
+            writer.into_inner(),
+            b">SEQ_ID_1\nACTCGATCGCGACGAA\n>SEQ_ID_2\nCATCGACTACGGCG\n"
+        );
+    }
+
+    #[test]
+    fn test_record_sink() {
+        let mut sink = RecordSink::fastq(Vec::new());
+
+        block_on(async {
+            for record in Fastq::<Vec<u8>>::new(FQ1) {
+                sink.send(record.unwrap()).await.unwrap();
+            }
+            SinkExt::<Record<&[u8], Vec<u8>>>::close(&mut sink)
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(sink.into_inner(), FQ1);
+
+        let mut sink = RecordSink::new(Vec::new(), FastaWriter::new(Vec::new()).line_width(8));
+
+        block_on(async {
+            for record in Fastq::<Vec<u8>>::new(FQ1) {
+                sink.feed(record.unwrap()).await.unwrap();
+            }
+            SinkExt::<Record<&[u8], Vec<u8>>>::flush(&mut sink)
+                .await
+                .unwrap();
+        });
+
+        assert_eq!(
+            sink.into_inner(),
+            b">SEQ_ID_1\nACTCGATC\nGCGACGAA\n>SEQ_ID_2\nCATCGACT\nACGGCG\n"
+        );
+    }
+
+    #[test]
+    fn test_interleaving() {
+        let r1: &[u8] = b"@pair_1/1\nACGT\n+\nIIII\n@pair_2/1\nGGCC\n+\nIIII\n";
+        let r2: &[u8] = b"@pair_1/2\nCCAA\n+\nIIII\n@pair_2/2\nTTGG\n+\nIIII\n";
+
+        let mut writer = InterleavedWriter::new(FastqWriter::new(Vec::new()));
+        let pairs = writer
+            .write_paired(Fastq::<Vec<u8>>::new(r1), Fastq::<Vec<u8>>::new(r2))
+            .unwrap();
+        assert_eq!(pairs, 2);
+
+        let interleaved = writer.into_inner().into_inner();
+        assert_eq!(
+            interleaved,
+            [&r1[..22], &r2[..22], &r1[22..], &r2[22..]].concat()
+        );
+
+        // and back again
+        let mut deinterleaver =
+            Deinterleaver::new(FastqWriter::new(Vec::new()), FastqWriter::new(Vec::new()));
+        let mut reader: FastqReader<Cursor<&[u8]>> = FastqReader::new(Cursor::new(&interleaved));
+        assert_eq!(deinterleaver.write_interleaved(&mut reader).unwrap(), 2);
+
+        let (w1, w2) = deinterleaver.into_inner();
+        assert_eq!(w1.into_inner(), r1);
+        assert_eq!(w2.into_inner(), r2);
+    }
+
+    #[test]
+    fn test_interleaving_errors() {
+        use crate::error::ParseError;
+
+        let r1: &[u8] = b"@pair_1/1\nACGT\n+\nIIII\n@pair_2/1\nGGCC\n+\nIIII\n";
+        let r2: &[u8] = b"@pair_1/2\nCCAA\n+\nIIII\n";
+
+        let mut writer = InterleavedWriter::new(FastqWriter::new(Vec::new()));
+        let e = writer
+            .write_paired(Fastq::<Vec<u8>>::new(r1), Fastq::<Vec<u8>>::new(r2))
+            .unwrap_err();
+        assert!(matches!(
+            e.parse_error(),
+            Some(ParseError::MissingMate(1, 2))
+        ));
+
+        // the pair before the error was written
+        assert_eq!(writer.get_ref().get_ref().len(), 44);
+
+        let mut deinterleaver =
+            Deinterleaver::new(FastqWriter::new(Vec::new()), FastqWriter::new(Vec::new()));
+        let e = deinterleaver
+            .write_interleaved(Fastq::<Vec<u8>>::new(r1))
+            .unwrap_err();
+        assert!(matches!(
+            e.parse_error(),
+            Some(ParseError::MismatchedMates(0))
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tokio_round_trip() {
+        use crate::fastq::AsyncFastqReader;
+        use futures::StreamExt;
+
+        // a small pipe makes the writer wait for the reader
+        let (tx, rx) = tokio::io::duplex(32);
+
+        let write = async move {
+            let mut sink = RecordSink::from_tokio(tx, FastqWriter::new(Vec::new()));
+            for record in Fastq::<Vec<u8>>::new(FQ1) {
+                sink.send(record.unwrap()).await.unwrap();
+            }
+            SinkExt::<Record<&[u8], Vec<u8>>>::close(&mut sink)
+                .await
+                .unwrap();
+        };
+
+        let reader: AsyncFastqReader<_> =
+            AsyncFastqReader::from_tokio(tokio::io::BufReader::new(rx));
+        let (records, ()) = tokio::join!(reader.collect::<Vec<_>>(), write);
+
+        let expected: Vec<_> =
+            Iterator::map(Fastq::<Vec<u8>>::new(FQ1), |r| r.unwrap().to_owned()).collect();
+        let records: Vec<Record<Vec<u8>>> = records.into_iter().map(Result::unwrap).collect();
+        assert_eq!(records, expected);
+    }
+}